@@ -0,0 +1,262 @@
+use std::{fmt, fs, io, ops::Index, path::Path};
+
+use bevy::prelude::*;
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+use serde::Deserialize;
+
+use crate::{assets::asset_path, boss::SEGMENT_BREAK, rules, words::WordsResource};
+
+pub const ENEMIES_FILE: &str = "enemies.ron";
+pub const DEFAULT_IMAGE: &str = "penguin.png";
+
+const BUILTIN_ENEMIES: &str = include_str!("../assets/enemies.ron");
+
+/// Every kind of enemy, in the order of the enemies file.
+#[derive(Resource, Debug)]
+pub struct EnemyArchetypes {
+    archetypes: Vec<EnemyArchetype>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct EnemyArchetype {
+    pub name: String,
+    #[serde(default = "default_image")]
+    pub image: String,
+    pub sprite: SpriteRegion,
+    pub blood: SpriteRegion,
+    pub speed: Speed,
+    pub points: u32,
+    #[serde(default = "default_damage")]
+    pub damage: u32, // igloo health lost when the enemy reaches it
+    pub words: WordPool,
+    #[serde(default)]
+    pub spawn_weights: Vec<SpawnWeight>, // sorted by level, empty when only spawned by others
+    #[serde(default)]
+    pub children: Option<ChildSpawning>,
+    #[serde(default)]
+    pub segment_pause: f32, // in seconds standing still after each typed segment of a sentence
+    #[serde(default)]
+    pub power_up: Option<PowerUp>, // granted to the player typing the word
+    #[serde(default)]
+    pub tint: Option<(f32, f32, f32)>, // rgb multiplied with the sprite
+
+    #[serde(skip)]
+    pub atlas: Handle<TextureAtlas>,
+    #[serde(skip)]
+    pub blood_atlas: Handle<TextureAtlas>,
+}
+
+/// Frames side by side in an image of the assets folder, in px.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct SpriteRegion {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub frames: usize,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct Speed {
+    pub base: f32,
+    pub per_level: f32,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordPool {
+    Short,
+    Long,
+    Special,
+    Sentence, // typed segment by segment
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerUp {
+    Freeze,     // every enemy stops for a while
+    Bomb,       // every enemy is defeated
+    SlowMotion, // every enemy walks slower for a while
+}
+
+/// Odds of the archetype from `from_level` on, relative to the other archetypes.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct SpawnWeight {
+    pub from_level: u16,
+    pub weight: f32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ChildSpawning {
+    pub archetype: String,
+    pub interval: f64, // in seconds
+}
+
+#[derive(Debug)]
+pub enum ArchetypesError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+    Empty,
+    NoFrame(String),
+    UnknownChild { archetype: String, child: String },
+}
+
+impl fmt::Display for ArchetypesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchetypesError::Io(error) => write!(f, "cannot read enemies file: {error}"),
+            ArchetypesError::Parse(error) => write!(f, "invalid enemies file: {error}"),
+            ArchetypesError::Empty => write!(f, "no enemy spawns on its own"),
+            ArchetypesError::NoFrame(archetype) => {
+                write!(f, "{archetype:?} has a sprite without frame")
+            }
+            ArchetypesError::UnknownChild { archetype, child } => {
+                write!(f, "{archetype:?} spawns unknown enemy {child:?}")
+            }
+        }
+    }
+}
+
+fn default_image() -> String {
+    DEFAULT_IMAGE.to_string()
+}
+
+fn default_damage() -> u32 {
+    1
+}
+
+impl Speed {
+    pub fn at_level(&self, level: u16) -> f32 {
+        rules::speed_at_level(self.base, self.per_level, level)
+    }
+}
+
+impl EnemyArchetype {
+    pub fn is_long(&self) -> bool {
+        self.words == WordPool::Long
+    }
+
+    pub fn color(&self) -> Color {
+        self.tint
+            .map_or(Color::WHITE, |(r, g, b)| Color::rgb(r, g, b))
+    }
+
+    pub fn spawn_weight(&self, level: u16) -> f32 {
+        // sorted by level, the last one reached applies
+        self.spawn_weights
+            .iter()
+            .rev()
+            .find(|spawn_weight| spawn_weight.from_level <= level)
+            .map_or(0.0, |spawn_weight| spawn_weight.weight)
+    }
+
+    pub fn word(&self, words: &WordsResource, rng: &mut impl Rng) -> String {
+        match self.words {
+            WordPool::Short => words.short_word(rng).to_string(),
+            WordPool::Long => words.long_word(rng).to_string(),
+            WordPool::Special => words.special_word(rng),
+            WordPool::Sentence => words.sentence(rng).join(&SEGMENT_BREAK.to_string()),
+        }
+    }
+}
+
+impl EnemyArchetypes {
+    /// Load the archetypes of `path`, relative to the assets folder. Problems
+    /// are reported and give the built-in archetypes.
+    pub fn from_file(path: impl AsRef<Path>) -> Self {
+        let path = asset_path(path);
+
+        let archetypes = fs::read_to_string(&path)
+            .map_err(ArchetypesError::Io)
+            .and_then(|content| Self::parse(&content));
+
+        archetypes.unwrap_or_else(|error| {
+            warn!("{}: {}", path.display(), error);
+            Self::builtin()
+        })
+    }
+
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN_ENEMIES).expect("invalid built-in enemies")
+    }
+
+    pub fn parse(content: &str) -> Result<Self, ArchetypesError> {
+        let mut archetypes: Vec<EnemyArchetype> =
+            ron::from_str(content).map_err(ArchetypesError::Parse)?;
+
+        for archetype in archetypes.iter_mut() {
+            archetype
+                .spawn_weights
+                .sort_by_key(|weight| weight.from_level);
+        }
+        let archetypes = Self { archetypes };
+
+        for archetype in archetypes.archetypes.iter() {
+            if archetype.sprite.frames == 0 || archetype.blood.frames == 0 {
+                return Err(ArchetypesError::NoFrame(archetype.name.clone()));
+            }
+
+            if let Some(child) = &archetype.children {
+                if archetypes.find(&child.archetype).is_none() {
+                    return Err(ArchetypesError::UnknownChild {
+                        archetype: archetype.name.clone(),
+                        child: child.archetype.clone(),
+                    });
+                }
+            }
+        }
+
+        if archetypes
+            .archetypes
+            .iter()
+            .all(|archetype| archetype.spawn_weights.is_empty())
+        {
+            return Err(ArchetypesError::Empty);
+        }
+
+        Ok(archetypes)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &EnemyArchetype> {
+        self.archetypes.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut EnemyArchetype> {
+        self.archetypes.iter_mut()
+    }
+
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.archetypes
+            .iter()
+            .position(|archetype| archetype.name == name)
+    }
+
+    /// Random archetype spawning on its own at `level`. Long enemies are only
+    /// picked when `long_allowed`.
+    pub fn pick(&self, level: u16, long_allowed: bool, rng: &mut impl Rng) -> Option<usize> {
+        let weights = self.archetypes.iter().map(|archetype| {
+            if archetype.is_long() && !long_allowed {
+                0.0
+            } else {
+                archetype.spawn_weight(level)
+            }
+        });
+
+        // fails when every weight is zero
+        let distribution = WeightedIndex::new(weights).ok()?;
+        Some(distribution.sample(rng))
+    }
+}
+
+impl Index<usize> for EnemyArchetypes {
+    type Output = EnemyArchetype;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.archetypes[index]
+    }
+}
+
+pub struct ArchetypesPlugin;
+impl Plugin for ArchetypesPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(EnemyArchetypes::from_file(ENEMIES_FILE));
+    }
+}
@@ -1,6 +1,20 @@
-use bevy::{audio::AudioSink, prelude::*};
-
-use crate::{defeat_zone::DEFEAT_ZONE_HEIGHT, CASE_SIZE};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    asset::{FileAssetIo, HandleId},
+    prelude::*,
+};
+
+use crate::{
+    archetypes::{EnemyArchetypes, SpriteRegion},
+    defeat_zone::DEFEAT_ZONE_HEIGHT,
+    mixer::SoundEffect,
+    music::{HEARTBEAT_TRACK, MUSIC_TRACKS},
+    CASE_SIZE,
+};
 
 #[derive(Resource)]
 pub struct EntitiesAtlas {
@@ -10,26 +24,41 @@ pub struct EntitiesAtlas {
     pub penguin_fired_throwed: Handle<TextureAtlas>,
 
     pub fish: usize,
-    // little spider animation
-    pub little_spider: Handle<TextureAtlas>,
-    pub big_spider: Handle<TextureAtlas>,
-
-    pub carot: Handle<TextureAtlas>,
 
     pub defeat_zone: usize,
-
-    pub little_enemy_blood: Handle<TextureAtlas>,
-    pub big_enemy_blood: Handle<TextureAtlas>,
 }
 
-#[derive(Resource)]
+#[derive(Resource, Default)]
 pub struct FontsAtlas {
     pub common_font: Handle<Font>,
 }
 
+impl EntitiesAtlas {
+    /// Atlases without any image, for the dedicated server.
+    pub fn headless() -> Self {
+        let handle = || Handle::weak(HandleId::random::<TextureAtlas>());
+
+        Self {
+            texture_atlas: handle(),
+            penguin_fired_standby: 0,
+            penguin_fired_throwed: handle(),
+            fish: 0,
+            defeat_zone: 0,
+        }
+    }
+}
+
+/// Sounds loaded at startup, played by the mixer.
 #[derive(Resource, Default)]
 pub struct AudioAtlas {
-    pub music: Option<Handle<AudioSink>>,
+    pub music: HashMap<&'static str, Handle<AudioSource>>, // by path
+    pub effects: HashMap<SoundEffect, Handle<AudioSource>>,
+}
+
+/// Resolve a path inside the assets folder, the same way the `AssetServer` does.
+/// Used for data files read directly from disk at startup.
+pub fn asset_path(path: impl AsRef<Path>) -> PathBuf {
+    FileAssetIo::get_base_path().join("assets").join(path)
 }
 
 pub struct AssetsPlugin;
@@ -43,6 +72,7 @@ fn load_assets(
     mut commands: Commands,
     assets: Res<AssetServer>,
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    mut archetypes: ResMut<EnemyArchetypes>,
 ) {
     let image_handle = assets.load("penguin.png");
     let mut atlas = TextureAtlas::new_empty(image_handle.clone(), Vec2::splat(256.0));
@@ -66,55 +96,28 @@ fn load_assets(
         max: Vec2::new(CASE_SIZE, CASE_SIZE * 2.0),
     });
 
-    let little_spider = texture_atlases.add(TextureAtlas::from_grid(
-        image_handle.clone(),
-        Vec2::splat(CASE_SIZE),
-        3,
-        1,
-        None,
-        Some(Vec2::new(CASE_SIZE * 4.0, 0.0)),
-    ));
-
-    let big_spider = texture_atlases.add(TextureAtlas::from_grid(
-        image_handle.clone(),
-        Vec2::new(CASE_SIZE, CASE_SIZE - 1.0),
-        3,
-        1,
-        None,
-        Some(Vec2::new(CASE_SIZE * 4.0, CASE_SIZE)),
-    ));
-
-    let carot = texture_atlases.add(TextureAtlas::from_grid(
-        image_handle.clone(),
-        Vec2::new(CASE_SIZE, CASE_SIZE - 1.0),
-        3,
-        1,
-        None,
-        Some(Vec2::new(CASE_SIZE, CASE_SIZE)),
-    ));
-
     let defeat_zone = atlas.add_texture(Rect {
         min: Vec2::new(0.0, CASE_SIZE * 2.0),
         max: Vec2::new(CASE_SIZE * 8.0, CASE_SIZE * 2.0 + DEFEAT_ZONE_HEIGHT),
     });
 
-    let little_enemy_blood = texture_atlases.add(TextureAtlas::from_grid(
-        image_handle.clone(),
-        Vec2::new(CASE_SIZE, CASE_SIZE),
-        3,
-        1,
-        None,
-        Some(Vec2::new(0.0, CASE_SIZE * 4.0)),
-    ));
-
-    let big_enemy_blood = texture_atlases.add(TextureAtlas::from_grid(
-        image_handle,
-        Vec2::new(CASE_SIZE, CASE_SIZE),
-        3,
-        1,
-        None,
-        Some(Vec2::new(0.0, CASE_SIZE * 5.0)),
-    ));
+    // enemy sprites are described by the enemies file
+    for archetype in archetypes.iter_mut() {
+        let image_handle = assets.load(archetype.image.as_str());
+        let region_atlas = |region: SpriteRegion| {
+            TextureAtlas::from_grid(
+                image_handle.clone(),
+                Vec2::new(region.width, region.height),
+                region.frames,
+                1,
+                None,
+                Some(Vec2::new(region.x, region.y)),
+            )
+        };
+
+        archetype.atlas = texture_atlases.add(region_atlas(archetype.sprite));
+        archetype.blood_atlas = texture_atlases.add(region_atlas(archetype.blood));
+    }
 
     let atlas_handle = texture_atlases.add(atlas);
 
@@ -123,15 +126,21 @@ fn load_assets(
         penguin_fired_standby,
         penguin_fired_throwed,
         fish,
-        little_spider,
-        big_spider,
-        carot,
         defeat_zone,
-        little_enemy_blood,
-        big_enemy_blood,
     });
     commands.insert_resource(FontsAtlas {
         common_font: assets.load("fonts/QuattrocentoSans-Regular.ttf"),
     });
-    commands.insert_resource(AudioAtlas::default())
+    commands.insert_resource(AudioAtlas {
+        music: MUSIC_TRACKS
+            .iter()
+            .map(|track| track.path)
+            .chain([HEARTBEAT_TRACK])
+            .map(|path| (path, assets.load(path)))
+            .collect(),
+        effects: SoundEffect::ALL
+            .into_iter()
+            .map(|effect| (effect, assets.load(effect.path())))
+            .collect(),
+    })
 }
@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+use bevy::{app::ScheduleRunnerSettings, log::LogPlugin, prelude::*};
+use penguin_game::{cli::ServerArgs, dedicated, network::NetworkPlugin};
+
+const TICK_RATE: f64 = 60.0; // simulation steps per second
+
+fn main() {
+    let mut app = App::new();
+    app.insert_resource(ScheduleRunnerSettings::run_loop(Duration::from_secs_f64(
+        1.0 / TICK_RATE,
+    )))
+    .add_plugins(MinimalPlugins)
+    .add_plugin(LogPlugin::default())
+    .add_plugin(TransformPlugin)
+    .add_plugin(HierarchyPlugin);
+
+    if let Err(error) = dedicated::setup_server(&mut app, ServerArgs::from_env()) {
+        error!("cannot start the server: {}", error);
+        return;
+    }
+
+    app.add_plugin(NetworkPlugin)
+        .add_plugin(dedicated::DedicatedServerPlugin);
+
+    app.run();
+}
@@ -0,0 +1,34 @@
+use bevy::{log::LogPlugin, prelude::*};
+use penguin_game::{
+    cli::SimArgs,
+    sim::{SimReport, Simulation},
+};
+
+/// Play many seeds with a bot and sum up how far it went, to judge balance
+/// changes of the levels and enemies files.
+fn main() {
+    let args = SimArgs::from_env();
+
+    let mut app = App::new();
+    // the problems of the data files are worth seeing, not the missing renderer
+    app.add_plugin(LogPlugin {
+        level: bevy::log::Level::WARN,
+        filter: "bevy_asset=off".to_string(),
+    });
+    let mut simulation = Simulation::from_app(app, args.bot);
+
+    let mut report = SimReport {
+        bot: args.bot,
+        runs: vec![],
+    };
+    for seed in args.first_seed..args.first_seed + args.seeds {
+        let run = simulation.run(seed, args.max_play_time);
+        eprintln!(
+            "seed {}: stage {}, score {}, {:.0}s",
+            run.seed, run.stage, run.score, run.play_time
+        );
+        report.runs.push(run);
+    }
+
+    println!("{report}");
+}
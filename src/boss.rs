@@ -0,0 +1,139 @@
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::prelude::*;
+
+use crate::{
+    enemy::{Enemy, EnemyState},
+    GameState,
+};
+
+pub const SEGMENT_BREAK: char = '\n'; // between the segments of an enemy word
+
+/// Sentence of a boss, typed one segment after the other. The segment being
+/// typed is the first section of the enemy text, the next ones are listed
+/// below it in the second section.
+#[derive(Component)]
+pub struct Segments {
+    pub next: VecDeque<String>,
+    pub total: usize,
+    pub stun: Timer, // standing still after each typed segment
+}
+
+/// Remaining segments shown above a boss.
+#[derive(Component)]
+pub struct BossHealthText;
+
+impl Segments {
+    pub fn new(next: VecDeque<String>, stun: Duration) -> Self {
+        Self {
+            total: next.len() + 1,
+            next,
+            stun: Timer::new(stun, TimerMode::Once),
+        }
+    }
+
+    /// Segments after the current one, each on its own line.
+    pub fn upcoming(&self) -> String {
+        self.next
+            .iter()
+            .map(|segment| format!("{SEGMENT_BREAK}{segment}"))
+            .collect()
+    }
+
+    /// Whole word left from `current`, as given to `spawn_enemy`.
+    pub fn word(&self, current: &str) -> String {
+        format!("{current}{}", self.upcoming())
+    }
+
+    /// Segments left, the current one counting until it is typed.
+    pub fn health(&self, current: &str) -> usize {
+        self.next.len() + usize::from(!current.is_empty())
+    }
+
+    /// Move to the next segment once the current one is typed, the enemy
+    /// standing still for a moment. Returns `false` after the last segment.
+    pub fn advance(&mut self, enemy: &mut Enemy, text: &mut Text) -> bool {
+        let Some(next) = self.next.pop_front() else {
+            return false;
+        };
+
+        text.sections[0].value = next;
+        text.sections[1].value = self.upcoming();
+        self.stun.reset();
+        enemy.state = EnemyState::Stunned;
+        true
+    }
+
+    /// Drop the typed characters until `remaining` are left, e.g. when another
+    /// player of a co-op game typed them.
+    pub fn skip_to(&mut self, enemy: &mut Enemy, text: &mut Text, remaining: usize) {
+        loop {
+            let current = &mut text.sections[0].value;
+            let left = current.chars().count()
+                + self.next.iter().map(|s| s.chars().count()).sum::<usize>();
+            if left <= remaining {
+                return;
+            }
+
+            if !current.is_empty() {
+                current.remove(0);
+            }
+            if text.sections[0].value.is_empty() && !self.advance(enemy, text) {
+                return;
+            }
+        }
+    }
+}
+
+pub struct BossPlugin;
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Gameplay)
+                .with_system(recover_stunned_bosses)
+                .with_system(update_boss_health),
+        );
+    }
+}
+
+pub fn recover_stunned_bosses(time: Res<Time>, mut bosses: Query<(&mut Enemy, &mut Segments)>) {
+    for (mut enemy, mut segments) in bosses.iter_mut() {
+        if enemy.state != EnemyState::Stunned {
+            continue;
+        }
+
+        segments.stun.tick(time.delta());
+        if segments.stun.finished() {
+            enemy.state = EnemyState::Walk;
+        }
+    }
+}
+
+fn update_boss_health(
+    bosses: Query<(&Segments, &Children)>,
+    mut texts: Query<&mut Text>,
+    health_texts: Query<(), With<BossHealthText>>,
+) {
+    for (segments, children) in bosses.iter() {
+        // the word is the first child
+        let Ok(word) = texts.get(children[0]) else {
+            continue;
+        };
+        let value = format!(
+            "HP {}/{}",
+            segments.health(&word.sections[0].value),
+            segments.total
+        );
+
+        for child in children
+            .iter()
+            .filter(|child| health_texts.contains(**child))
+        {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                if text.sections[0].value != value {
+                    text.sections[0].value = value.clone();
+                }
+            }
+        }
+    }
+}
@@ -1,20 +1,74 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, render::camera::ScalingMode};
+
+use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
+
+pub const LETTERBOX_COLOR: Color = Color::rgb(0.12, 0.13, 0.15);
+const LETTERBOX_SIZE: f32 = 10_000.0; // past the sides of any window
+const LETTERBOX_Z: f32 = 900.0; // over the field and the HUD
 
 #[derive(Component)]
 pub struct GameCamera;
 
+/// Bar hiding what is outside of the playfield, on the sides of a wide window
+/// or above and below a tall one.
+#[derive(Component)]
+pub struct Letterbox;
+
 pub struct GameCameraPlugin;
 
 impl Plugin for GameCameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system_to_stage(StartupStage::PreStartup, spawn_camera);
+        app.add_startup_system_to_stage(StartupStage::PreStartup, spawn_camera)
+            .add_startup_system(spawn_letterbox)
+            .add_system(scale_ui);
     }
 }
 
+/// The whole playfield is shown whatever the window size, scaled by the
+/// camera.
 fn spawn_camera(mut commands: Commands) {
+    let mut camera = Camera2dBundle::default();
+    camera.projection.scaling_mode = ScalingMode::Auto {
+        min_width: WINDOW_WIDTH,
+        min_height: WINDOW_HEIGHT,
+    };
+
     commands
-        .spawn(Camera2dBundle::default())
+        .spawn(camera)
         .insert(GameCamera)
         .insert(Name::new("Camera"))
         .insert(VisibilityBundle::default());
 }
+
+fn spawn_letterbox(mut commands: Commands) {
+    let playfield = Vec2::new(WINDOW_WIDTH, WINDOW_HEIGHT);
+
+    for side in [Vec2::X, Vec2::NEG_X, Vec2::Y, Vec2::NEG_Y] {
+        let position = side * (playfield + LETTERBOX_SIZE) / 2.0;
+        commands
+            .spawn(SpriteBundle {
+                sprite: Sprite {
+                    color: LETTERBOX_COLOR,
+                    custom_size: Some(Vec2::splat(LETTERBOX_SIZE)),
+                    ..default()
+                },
+                transform: Transform::from_translation(position.extend(LETTERBOX_Z)),
+                ..default()
+            })
+            .insert(Letterbox)
+            .insert(Name::new("Letterbox"));
+    }
+}
+
+/// The menus grow and shrink with the window, like the playfield.
+fn scale_ui(windows: Res<Windows>, mut ui_scale: ResMut<UiScale>) {
+    let Some(window) = windows.get_primary() else {
+        return;
+    };
+
+    let scale = (window.width() / WINDOW_WIDTH).min(window.height() / WINDOW_HEIGHT) as f64;
+    // a minimized window has no size
+    if scale > 0.0 && ui_scale.scale != scale {
+        ui_scale.scale = scale;
+    }
+}
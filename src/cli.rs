@@ -0,0 +1,190 @@
+use std::{env, net::SocketAddr, path::PathBuf};
+
+use bevy::prelude::*;
+
+use crate::{
+    network::{MatchMode, COOP_MAX_PLAYERS, COOP_MIN_PLAYERS},
+    sim::BotTypist,
+};
+
+pub const DEFAULT_SERVER_ADDR: &str = "127.0.0.1:5000";
+pub const DEFAULT_PLAYER_NAME: &str = "Penguin";
+
+/// Command line options, e.g. `client --server 192.168.1.20:5000 --name Tux --seed 42`.
+/// `--replay run.ron` is the replay watched from the main menu, `--ghost run.ron`
+/// a replay to race, e.g. of a teammate.
+#[derive(Resource, Debug, Clone)]
+pub struct Args {
+    pub server_addr: SocketAddr, // address hosted or joined in multiplayer
+    pub player_name: String,
+    pub seed: Option<u64>,       // of every solo run, random when `None`
+    pub replay: Option<PathBuf>, // watched instead of the most recent one
+    pub ghost: Option<PathBuf>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            server_addr: DEFAULT_SERVER_ADDR.parse().unwrap(),
+            player_name: DEFAULT_PLAYER_NAME.to_string(),
+            seed: None,
+            replay: None,
+            ghost: None,
+        }
+    }
+}
+
+impl Args {
+    pub fn from_env() -> Self {
+        Self::parse(env::args().skip(1))
+    }
+
+    /// Unknown or malformed options are reported and ignored.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Self {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let value = args.next();
+
+            match (arg.as_str(), value) {
+                ("--server", Some(value)) => match value.parse() {
+                    Ok(addr) => parsed.server_addr = addr,
+                    Err(_) => eprintln!("--server expects an address like {DEFAULT_SERVER_ADDR}"),
+                },
+                ("--name", Some(value)) => parsed.player_name = value,
+                ("--seed", Some(value)) => match value.parse() {
+                    Ok(seed) => parsed.seed = Some(seed),
+                    Err(_) => eprintln!("--seed expects a positive number"),
+                },
+                ("--replay", Some(value)) => parsed.replay = Some(value.into()),
+                ("--ghost", Some(value)) => parsed.ghost = Some(value.into()),
+                (arg, _) => eprintln!("ignoring unknown argument {arg}"),
+            }
+        }
+
+        parsed
+    }
+}
+
+/// Dedicated server options, e.g. `server --server 192.168.1.20:5000 --mode coop --players 3`.
+/// The address is the one the players connect to.
+#[derive(Resource, Debug, Clone)]
+pub struct ServerArgs {
+    pub public_addr: SocketAddr,
+    pub mode: MatchMode,
+    pub players: usize, // co-op games start once this many players joined
+}
+
+impl Default for ServerArgs {
+    fn default() -> Self {
+        Self {
+            public_addr: DEFAULT_SERVER_ADDR.parse().unwrap(),
+            mode: MatchMode::Coop,
+            players: COOP_MIN_PLAYERS,
+        }
+    }
+}
+
+impl ServerArgs {
+    pub fn from_env() -> Self {
+        Self::parse(env::args().skip(1))
+    }
+
+    /// Unknown or malformed options are reported and ignored.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Self {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let value = args.next();
+
+            match (arg.as_str(), value.as_deref()) {
+                ("--server", Some(value)) => match value.parse() {
+                    Ok(addr) => parsed.public_addr = addr,
+                    Err(_) => eprintln!("--server expects an address like {DEFAULT_SERVER_ADDR}"),
+                },
+                ("--mode", Some("coop")) => parsed.mode = MatchMode::Coop,
+                ("--mode", Some("versus")) => parsed.mode = MatchMode::Versus,
+                ("--mode", _) => eprintln!("--mode expects coop or versus"),
+                ("--players", Some(value)) => match value.parse() {
+                    Ok(players) if (COOP_MIN_PLAYERS..=COOP_MAX_PLAYERS).contains(&players) => {
+                        parsed.players = players
+                    }
+                    _ => eprintln!(
+                        "--players expects a number from {COOP_MIN_PLAYERS} to {COOP_MAX_PLAYERS}"
+                    ),
+                },
+                (arg, _) => eprintln!("ignoring unknown argument {arg}"),
+            }
+        }
+
+        parsed
+    }
+}
+
+/// Balancing simulation options, e.g. `sim --seeds 200 --wpm 55 --errors 0.03 --reaction 0.4`.
+#[derive(Debug, Clone)]
+pub struct SimArgs {
+    pub first_seed: u64,
+    pub seeds: u64,         // number of runs, of the seeds following `first_seed`
+    pub max_play_time: f32, // in seconds, the runs going on are stopped
+    pub bot: BotTypist,
+}
+
+impl Default for SimArgs {
+    fn default() -> Self {
+        Self {
+            first_seed: 0,
+            seeds: 100,
+            max_play_time: 1200.0,
+            bot: BotTypist::default(),
+        }
+    }
+}
+
+impl SimArgs {
+    pub fn from_env() -> Self {
+        Self::parse(env::args().skip(1))
+    }
+
+    /// Unknown or malformed options are reported and ignored.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Self {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let value = args.next();
+
+            match (arg.as_str(), value.as_deref()) {
+                ("--first-seed", Some(value)) => match value.parse() {
+                    Ok(seed) => parsed.first_seed = seed,
+                    Err(_) => eprintln!("--first-seed expects a positive number"),
+                },
+                ("--seeds", Some(value)) => match value.parse() {
+                    Ok(seeds) if seeds > 0 => parsed.seeds = seeds,
+                    _ => eprintln!("--seeds expects a number of runs"),
+                },
+                ("--max-time", Some(value)) => match value.parse() {
+                    Ok(seconds) if seconds > 0.0 => parsed.max_play_time = seconds,
+                    _ => eprintln!("--max-time expects a number of seconds"),
+                },
+                ("--wpm", Some(value)) => match value.parse() {
+                    Ok(wpm) if wpm > 0.0 => parsed.bot.wpm = wpm,
+                    _ => eprintln!("--wpm expects a number of words per minute"),
+                },
+                ("--errors", Some(value)) => match value.parse() {
+                    Ok(rate) if (0.0..=1.0).contains(&rate) => parsed.bot.error_rate = rate,
+                    _ => eprintln!("--errors expects a rate from 0.0 to 1.0"),
+                },
+                ("--reaction", Some(value)) => match value.parse() {
+                    Ok(seconds) if seconds >= 0.0 => parsed.bot.reaction = seconds,
+                    _ => eprintln!("--reaction expects a number of seconds"),
+                },
+                (arg, _) => eprintln!("ignoring unknown argument {arg}"),
+            }
+        }
+
+        parsed
+    }
+}
@@ -0,0 +1,678 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use bevy::prelude::*;
+use bevy_renet::renet::{RenetClient, RenetServer};
+
+use crate::{
+    archetypes::EnemyArchetypes,
+    assets::{EntitiesAtlas, FontsAtlas},
+    boss::{Segments, SEGMENT_BREAK},
+    enemy::{
+        spawn_enemy, throw_fish, ClaimedBy, Enemy, EnemyAnimated, EnemyHit, EnemyKind, EnemyState,
+        GeneratedEnemy, Target, TargetLocked,
+    },
+    gameover::GameoverUIRoot,
+    lobby::ConnectionLost,
+    network::{
+        encode, ClientMessage, EnemyClaim, PlayerInfo, PlayerScoreInfo, ServerLobby, ServerMessage,
+        SpawnedEnemy, PLAYER_COLORS, RELIABLE_CHANNEL, UNRELIABLE_CHANNEL,
+    },
+    penguins::FishThrowed,
+    player::{PlayerScore, PlayerStats},
+    request_game_over,
+    stage::StageComponent,
+    words::TextEnemy,
+    GameMode, GameState, WINDOW_HEIGHT, WINDOW_WIDTH,
+};
+
+pub const POSITIONS_SHARE_INTERVAL: u64 = 250; // in ms
+
+#[derive(Resource)]
+pub struct CoopMatch {
+    pub me: u64,
+    pub players: Vec<PlayerInfo>,
+    pub scores: Vec<PlayerScoreInfo>,
+    pub connection_lost: bool,
+
+    // only used by the host
+    level: u16,
+    next_enemy_id: u32,
+    positions_timer: Timer,
+}
+
+impl CoopMatch {
+    pub fn new(me: u64, players: &[PlayerInfo]) -> Self {
+        Self {
+            me,
+            players: players.to_vec(),
+            scores: vec![],
+            connection_lost: false,
+            level: 1,
+            next_enemy_id: 0,
+            positions_timer: Timer::new(
+                Duration::from_millis(POSITIONS_SHARE_INTERVAL),
+                TimerMode::Repeating,
+            ),
+        }
+    }
+
+    pub fn player_color(&self, id: u64) -> Color {
+        self.players
+            .iter()
+            .find(|player| player.id == id)
+            .map(|player| PLAYER_COLORS[player.color])
+            .unwrap_or(Color::BLACK)
+    }
+}
+
+/// Identify an enemy of the shared field across the players.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NetworkId(pub u32);
+
+#[derive(Component)]
+pub struct CoopScoreboardText;
+
+#[derive(Component)]
+pub struct CoopResultText;
+
+pub struct CoopPlugin;
+impl Plugin for CoopPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(CoopHostPlugin)
+            .add_system_set(
+                SystemSet::on_update(GameState::Gameplay)
+                    .with_system(spawn_shared_enemies)
+                    .with_system(sync_positions)
+                    .with_system(send_typing)
+                    .with_system(receive_claims)
+                    .with_system(receive_typing)
+                    .with_system(send_score)
+                    .with_system(update_scoreboard)
+                    .with_system(receive_team_defeat)
+                    .with_system(lose_coop),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::GameOver)
+                    .with_system(update_scoreboard)
+                    .with_system(show_coop_result),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::GameOver).with_system(despawn_scoreboard),
+            );
+    }
+}
+
+/// Share the simulated field with the players, used by the co-op host and the
+/// dedicated server.
+pub struct CoopHostPlugin;
+impl Plugin for CoopHostPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Gameplay)
+                .with_system(forget_gone_enemies.before(share_spawned_enemies))
+                .with_system(share_spawned_enemies)
+                .with_system(share_positions)
+                .with_system(share_level),
+        )
+        .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(share_team_defeat));
+    }
+}
+
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+fn share_spawned_enemies(
+    mut commands: Commands,
+    mode: Res<GameMode>,
+    coop: Option<ResMut<CoopMatch>>,
+    server: Option<ResMut<RenetServer>>,
+    lobby: Option<ResMut<ServerLobby>>,
+    enemies: Query<
+        (
+            Entity,
+            &Enemy,
+            &Transform,
+            &Children,
+            &EnemyKind,
+            Option<&GeneratedEnemy>,
+            Option<&Segments>,
+        ),
+        Without<NetworkId>,
+    >,
+    texts: Query<&Text>,
+    archetypes: Res<EnemyArchetypes>,
+) {
+    let (Some(mut coop), Some(mut server), Some(mut lobby)) = (coop, server, lobby) else {
+        return;
+    };
+
+    if *mode != GameMode::CoopHost {
+        return;
+    }
+
+    for (entity, enemy, transform, children, kind, generated, segments) in enemies.iter() {
+        let Ok(text) = texts.get(children[0]) else {
+            continue;
+        };
+
+        let id = coop.next_enemy_id;
+        coop.next_enemy_id += 1;
+        commands.entity(entity).insert(NetworkId(id));
+
+        // the segments of a boss are typed as a single word
+        let current = &text.sections[0].value;
+        let word = segments.map_or_else(|| current.clone(), |segments| segments.word(current));
+        lobby.claims.insert(
+            id,
+            EnemyClaim {
+                owner: None,
+                remaining: word.replace(SEGMENT_BREAK, ""),
+            },
+        );
+
+        let archetype = &archetypes[kind.0];
+        let spawned = SpawnedEnemy {
+            id,
+            word,
+            position: (transform.translation.x, transform.translation.y),
+            velocity: enemy.velocity,
+            points: enemy.points,
+            archetype: archetype.name.clone(),
+            long_enemy: archetype.is_long(),
+            generated: generated.is_some(),
+        };
+        server.broadcast_message(
+            RELIABLE_CHANNEL,
+            encode(&ServerMessage::EnemySpawned(spawned)),
+        );
+    }
+}
+
+/// Enemies despawned before their word was typed, e.g. when they reached the
+/// igloo, can't be claimed anymore.
+fn forget_gone_enemies(lobby: Option<ResMut<ServerLobby>>, enemies: Query<&NetworkId>) {
+    let Some(mut lobby) = lobby else {
+        return;
+    };
+
+    let alive = enemies.iter().map(|id| id.0).collect::<HashSet<_>>();
+    lobby.claims.retain(|id, _| alive.contains(id));
+}
+
+/// Enemies walk on every field, the host only corrects the drift.
+fn share_positions(
+    mode: Res<GameMode>,
+    time: Res<Time>,
+    coop: Option<ResMut<CoopMatch>>,
+    server: Option<ResMut<RenetServer>>,
+    enemies: Query<(&NetworkId, &Transform)>,
+) {
+    let (Some(mut coop), Some(mut server)) = (coop, server) else {
+        return;
+    };
+
+    if *mode != GameMode::CoopHost || !coop.positions_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let positions = enemies
+        .iter()
+        .map(|(id, transform)| (id.0, (transform.translation.x, transform.translation.y)))
+        .collect();
+    server.broadcast_message(
+        UNRELIABLE_CHANNEL,
+        encode(&ServerMessage::Positions(positions)),
+    );
+}
+
+fn share_level(
+    mode: Res<GameMode>,
+    coop: Option<ResMut<CoopMatch>>,
+    server: Option<ResMut<RenetServer>>,
+    stage: Query<&StageComponent, Changed<StageComponent>>,
+) {
+    let (Some(mut coop), Some(mut server)) = (coop, server) else {
+        return;
+    };
+
+    let Ok(stage) = stage.get_single() else {
+        return;
+    };
+
+    if *mode != GameMode::CoopHost || stage.level <= coop.level {
+        return;
+    }
+
+    coop.level = stage.level;
+    server.broadcast_message(
+        RELIABLE_CHANNEL,
+        encode(&ServerMessage::LevelUp { level: stage.level }),
+    );
+}
+
+fn spawn_shared_enemies(
+    mut commands: Commands,
+    mut messages: EventReader<ServerMessage>,
+    mode: Res<GameMode>,
+    mut stage: Query<&mut StageComponent>,
+    time: Res<Time>,
+    archetypes: Res<EnemyArchetypes>,
+    fonts: Res<FontsAtlas>,
+) {
+    if !mode.is_replica() {
+        return;
+    }
+
+    for message in messages.iter() {
+        let ServerMessage::EnemySpawned(spawned) = message else {
+            continue;
+        };
+
+        // the host may know enemies missing here
+        let kind = archetypes.find(&spawned.archetype).unwrap_or_default();
+
+        let enemy = spawn_enemy(
+            &mut commands,
+            time.elapsed_seconds_f64(),
+            Vec2::new(spawned.position.0, spawned.position.1),
+            spawned.velocity,
+            spawned.points,
+            spawned.word.clone(),
+            EnemyKind(kind),
+            &archetypes,
+            &fonts,
+        );
+        commands.entity(enemy).insert(NetworkId(spawned.id));
+
+        if spawned.generated {
+            commands.entity(enemy).insert(GeneratedEnemy);
+        } else {
+            stage.single_mut().enemy_born(spawned.long_enemy);
+        }
+    }
+}
+
+fn sync_positions(
+    mut messages: EventReader<ServerMessage>,
+    mode: Res<GameMode>,
+    mut enemies: Query<(&NetworkId, &mut Transform)>,
+) {
+    if !mode.is_replica() {
+        return;
+    }
+
+    for message in messages.iter() {
+        let ServerMessage::Positions(positions) = message else {
+            continue;
+        };
+
+        let positions = positions.iter().copied().collect::<HashMap<_, _>>();
+        for (id, mut transform) in enemies.iter_mut() {
+            if let Some((x, y)) = positions.get(&id.0) {
+                transform.translation.x = *x;
+                transform.translation.y = *y;
+            }
+        }
+    }
+}
+
+/// Claim the enemies targeted by `key_pressed` and share every hit.
+fn send_typing(
+    mut locked: EventReader<TargetLocked>,
+    mut hits: EventReader<EnemyHit>,
+    coop: Option<Res<CoopMatch>>,
+    client: Option<ResMut<RenetClient>>,
+    ids: Query<&NetworkId>,
+) {
+    let (Some(_), Some(mut client)) = (coop, client) else {
+        return;
+    };
+
+    for locked in locked.iter() {
+        if let Ok(id) = ids.get(locked.enemy) {
+            let message = ClientMessage::Claim { enemy: id.0 };
+            client.send_message(RELIABLE_CHANNEL, encode(&message));
+        }
+    }
+
+    for hit in hits.iter() {
+        if let Ok(id) = ids.get(hit.enemy) {
+            let message = ClientMessage::Typed {
+                enemy: id.0,
+                char: hit.char,
+            };
+            client.send_message(RELIABLE_CHANNEL, encode(&message));
+        }
+    }
+}
+
+/// Another player holds the enemy, or it is free again: give it the color of
+/// its owner and the word left to type on the server.
+#[allow(clippy::type_complexity)]
+fn receive_claims(
+    mut commands: Commands,
+    mut messages: EventReader<ServerMessage>,
+    coop: Option<Res<CoopMatch>>,
+    mut enemies: Query<(
+        Entity,
+        &NetworkId,
+        &mut Enemy,
+        &Children,
+        Option<&Target>,
+        Option<&mut Segments>,
+    )>,
+    mut texts: Query<&mut Text, With<TextEnemy>>,
+    fishes: Query<&FishThrowed>,
+) {
+    let Some(coop) = coop else {
+        return;
+    };
+
+    for message in messages.iter() {
+        let ServerMessage::Claimed {
+            enemy: enemy_id,
+            owner,
+            remaining,
+        } = message
+        else {
+            continue;
+        };
+
+        if *owner == Some(coop.me) {
+            continue;
+        }
+
+        let Some((entity, _, mut enemy, children, target, segments)) =
+            enemies.iter_mut().find(|(_, id, ..)| id.0 == *enemy_id)
+        else {
+            continue;
+        };
+        let Ok(mut text) = texts.get_mut(children[0]) else {
+            continue;
+        };
+
+        if target.is_some() {
+            commands.entity(entity).remove::<Target>();
+            text.sections[0].style.font_size /= 1.25;
+        }
+
+        match owner {
+            Some(owner) => {
+                commands.entity(entity).insert(ClaimedBy);
+                text.sections[0].style.color = coop.player_color(*owner);
+            }
+            None => {
+                commands.entity(entity).remove::<ClaimedBy>();
+                text.sections[0].style.color = Color::BLACK;
+            }
+        }
+
+        // the fishes already thrown at the enemy will still hit it
+        let fishes = fishes.iter().filter(|fish| fish.target == entity).count();
+        match segments {
+            Some(mut segments) => {
+                segments.skip_to(&mut enemy, &mut text, remaining.chars().count())
+            }
+            None => text.sections[0].value = remaining.clone(),
+        }
+        enemy.life = remaining.chars().count() + fishes;
+    }
+}
+
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+fn receive_typing(
+    mut commands: Commands,
+    mut messages: EventReader<ServerMessage>,
+    coop: Option<Res<CoopMatch>>,
+    mut enemies: Query<(
+        Entity,
+        &NetworkId,
+        &mut Enemy,
+        &Children,
+        Option<&GeneratedEnemy>,
+        Option<&mut Segments>,
+    )>,
+    mut texts: Query<(&mut Text, &GlobalTransform), With<TextEnemy>>,
+    mut stage: Query<&mut StageComponent>,
+    atlases: Res<EntitiesAtlas>,
+) {
+    let Some(coop) = coop else {
+        return;
+    };
+
+    for message in messages.iter() {
+        let ServerMessage::Typed {
+            enemy: enemy_id,
+            player,
+        } = message
+        else {
+            continue;
+        };
+
+        if *player == coop.me {
+            continue;
+        }
+
+        let Some((entity, _, mut enemy, children, generated, segments)) =
+            enemies.iter_mut().find(|(_, id, ..)| id.0 == *enemy_id)
+        else {
+            continue;
+        };
+        let Ok((mut text, transform)) = texts.get_mut(children[0]) else {
+            continue;
+        };
+
+        if text.sections[0].value.is_empty() {
+            continue;
+        }
+        text.sections[0].value.remove(0);
+
+        throw_fish(
+            &mut commands,
+            &atlases,
+            entity,
+            transform.translation().truncate(),
+        );
+
+        // the team progresses with the enemies defeated by any player, a boss
+        // only after its last segment
+        let next_segment = text.sections[0].value.is_empty()
+            && segments.is_some_and(|mut segments| segments.advance(&mut enemy, &mut text));
+        if text.sections[0].value.is_empty() && !next_segment {
+            commands
+                .entity(entity)
+                .remove::<EnemyAnimated>()
+                .remove::<ClaimedBy>();
+            enemy.state = EnemyState::Idle;
+
+            if generated.is_none() {
+                stage.single_mut().enemy_defeated();
+            }
+        }
+    }
+}
+
+fn send_score(
+    coop: Option<Res<CoopMatch>>,
+    client: Option<ResMut<RenetClient>>,
+    score: Res<PlayerScore>,
+    player_stats: Res<PlayerStats>,
+) {
+    let (Some(_), Some(mut client)) = (coop, client) else {
+        return;
+    };
+
+    if score.is_changed() || player_stats.is_changed() {
+        let message = ClientMessage::Score {
+            score: score.0,
+            combos: player_stats.combo.count,
+        };
+        client.send_message(RELIABLE_CHANNEL, encode(&message));
+    }
+}
+
+fn update_scoreboard(
+    mut commands: Commands,
+    mut messages: EventReader<ServerMessage>,
+    coop: Option<ResMut<CoopMatch>>,
+    mut scoreboard: Query<&mut Text, With<CoopScoreboardText>>,
+    fonts: Res<FontsAtlas>,
+) {
+    let Some(mut coop) = coop else {
+        return;
+    };
+
+    for message in messages.iter() {
+        if let ServerMessage::Scoreboard(scores) = message {
+            coop.scores = scores.clone();
+        }
+    }
+
+    let sections = scoreboard_sections(&coop, &fonts, 18.0);
+
+    match scoreboard.get_single_mut() {
+        Ok(mut text) if coop.is_changed() => text.sections = sections,
+        Ok(_) => {}
+        Err(_) => {
+            commands
+                .spawn(Text2dBundle {
+                    text: Text::from_sections(sections).with_alignment(TextAlignment::TOP_LEFT),
+                    transform: Transform::from_translation(Vec3::new(
+                        -WINDOW_WIDTH / 2.0 + 10.0,
+                        WINDOW_HEIGHT / 2.0 - 10.0,
+                        5.0,
+                    )),
+                    ..default()
+                })
+                .insert(Name::new("Co-op Scoreboard"))
+                .insert(CoopScoreboardText);
+        }
+    }
+}
+
+fn despawn_scoreboard(mut commands: Commands, scoreboard: Query<Entity, With<CoopScoreboardText>>) {
+    for entity in scoreboard.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// One line per player, best score first, in the color of the player.
+fn scoreboard_sections(coop: &CoopMatch, fonts: &FontsAtlas, font_size: f32) -> Vec<TextSection> {
+    let score_of = |id: u64| coop.scores.iter().find(|score| score.id == id);
+
+    let mut players = coop.players.iter().collect::<Vec<_>>();
+    players.sort_by_key(|player| std::cmp::Reverse(score_of(player.id).map(|s| s.score)));
+
+    players
+        .iter()
+        .map(|player| {
+            let (score, combos) = score_of(player.id)
+                .map(|score| (score.score, score.combos))
+                .unwrap_or_default();
+
+            TextSection::new(
+                format!("{} {} (x{})\n", player.name, score, combos),
+                TextStyle {
+                    font: fonts.common_font.clone(),
+                    font_size,
+                    color: PLAYER_COLORS[player.color],
+                },
+            )
+        })
+        .collect()
+}
+
+fn receive_team_defeat(
+    mut messages: EventReader<ServerMessage>,
+    mode: Res<GameMode>,
+    mut state: ResMut<State<GameState>>,
+) {
+    if !mode.is_replica() {
+        return;
+    }
+
+    for message in messages.iter() {
+        if let ServerMessage::TeamDefeated = message {
+            request_game_over(&mut state);
+            return;
+        }
+    }
+}
+
+fn lose_coop(
+    mut lost: EventReader<ConnectionLost>,
+    coop: Option<ResMut<CoopMatch>>,
+    mut state: ResMut<State<GameState>>,
+) {
+    let Some(mut coop) = coop else {
+        return;
+    };
+
+    if lost.iter().next().is_some() {
+        coop.connection_lost = true;
+        request_game_over(&mut state);
+    }
+}
+
+/// An enemy reached the defeat zone of the host, the whole team lost.
+fn share_team_defeat(
+    mode: Res<GameMode>,
+    server: Option<ResMut<RenetServer>>,
+    lobby: Option<ResMut<ServerLobby>>,
+) {
+    let (Some(mut server), Some(mut lobby)) = (server, lobby) else {
+        return;
+    };
+
+    if *mode == GameMode::CoopHost {
+        lobby.finished = true;
+        server.broadcast_message(RELIABLE_CHANNEL, encode(&ServerMessage::TeamDefeated));
+    }
+}
+
+fn show_coop_result(
+    mut commands: Commands,
+    coop: Option<Res<CoopMatch>>,
+    root: Query<Entity, With<GameoverUIRoot>>,
+    result_text: Query<Entity, With<CoopResultText>>,
+    fonts: Res<FontsAtlas>,
+) {
+    let Some(coop) = coop else {
+        return;
+    };
+
+    if !result_text.is_empty() || root.is_empty() {
+        return;
+    }
+
+    let title = if coop.connection_lost {
+        "Connection lost\n"
+    } else {
+        "Team defeated\n"
+    };
+    let mut sections = vec![TextSection::new(
+        title,
+        TextStyle {
+            font: fonts.common_font.clone(),
+            font_size: 40.0,
+            color: Color::BLACK,
+        },
+    )];
+    sections.extend(scoreboard_sections(&coop, &fonts, 30.0));
+
+    let text = commands
+        .spawn(TextBundle {
+            style: Style {
+                align_self: AlignSelf::Center,
+                margin: UiRect::all(Val::Percent(3.0)),
+                ..default()
+            },
+            text: Text::from_sections(sections).with_alignment(TextAlignment::CENTER),
+            ..default()
+        })
+        .insert(CoopResultText)
+        .id();
+
+    commands.entity(root.single()).add_child(text);
+}
@@ -0,0 +1,177 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use chrono::{Datelike, NaiveDate};
+
+use crate::{
+    assets::FontsAtlas,
+    gameover::GameoverUIRoot,
+    high_scores::{config_dir, HighScores, HighScoresError},
+    GameState,
+};
+
+pub const DAILY_SCORES_FILE: &str = "daily_scores.ron";
+pub const DAILY_ATTEMPT_FILE: &str = "daily_attempt.ron";
+
+/// Leaderboard of the daily challenges, next to the regular high scores.
+#[derive(Resource)]
+pub struct DailyScores {
+    pub scores: HighScores,
+    last_attempt: Option<String>, // YYYY-MM-DD of the last ranked daily challenge
+    attempt_path: Option<PathBuf>,
+}
+
+/// Solo run of the day: every player gets the same enemies and words.
+#[derive(Resource)]
+pub struct DailyChallenge {
+    pub date: String, // YYYY-MM-DD, local time
+    pub seed: u64,
+    pub ranked: bool, // only the first attempt of the day is
+}
+
+#[derive(Component)]
+pub struct DailyChallengeText;
+
+impl DailyChallenge {
+    pub fn today() -> Self {
+        Self::on(chrono::Local::now().date_naive())
+    }
+
+    pub fn on(date: NaiveDate) -> Self {
+        Self {
+            date: date.format("%Y-%m-%d").to_string(),
+            seed: date.num_days_from_ce() as u64,
+            ranked: false,
+        }
+    }
+}
+
+impl DailyScores {
+    /// Load the leaderboard and the last attempt of the user config directory.
+    /// Problems are reported, an unreadable attempt is forgotten.
+    pub fn from_config_dir() -> Self {
+        let attempt_path = config_dir().map(|dir| dir.join(DAILY_ATTEMPT_FILE));
+        let last_attempt = attempt_path.as_deref().and_then(|path| {
+            load_attempt(path).unwrap_or_else(|error| {
+                warn!("{}: {}", path.display(), error);
+                None
+            })
+        });
+
+        Self {
+            scores: HighScores::from_config_file(DAILY_SCORES_FILE),
+            last_attempt,
+            attempt_path,
+        }
+    }
+
+    pub fn attempted_on(&self, date: &str) -> bool {
+        self.last_attempt.as_deref() == Some(date)
+    }
+
+    /// Remember the ranked attempt of `date`, saved right away.
+    pub fn record_attempt(&mut self, date: &str) -> Result<(), HighScoresError> {
+        self.last_attempt = Some(date.to_string());
+
+        let Some(path) = &self.attempt_path else {
+            return Ok(());
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(HighScoresError::Io)?;
+        }
+
+        let content = ron::to_string(&self.last_attempt).map_err(HighScoresError::Serialize)?;
+        fs::write(path, content).map_err(HighScoresError::Io)
+    }
+}
+
+fn load_attempt(path: &Path) -> Result<Option<String>, HighScoresError> {
+    match fs::read_to_string(path) {
+        Ok(content) => ron::from_str(&content).map_err(HighScoresError::Parse),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(HighScoresError::Io(error)),
+    }
+}
+
+pub struct DailyPlugin;
+impl Plugin for DailyPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(DailyScores::from_config_dir())
+            .add_system_set(SystemSet::on_enter(GameState::Gameplay).with_system(start_attempt))
+            .add_system_set(
+                SystemSet::on_update(GameState::GameOver).with_system(spawn_daily_challenge_text),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::MainMenu).with_system(leave_daily_challenge),
+            );
+    }
+}
+
+/// The first attempt of the day is ranked, even when it is given up.
+fn start_attempt(daily: Option<ResMut<DailyChallenge>>, mut daily_scores: ResMut<DailyScores>) {
+    let Some(mut daily) = daily else {
+        return;
+    };
+
+    daily.ranked = !daily_scores.attempted_on(&daily.date);
+    if daily.ranked {
+        if let Err(error) = daily_scores.record_attempt(&daily.date) {
+            error!("{}", error);
+        }
+    }
+}
+
+fn spawn_daily_challenge_text(
+    mut commands: Commands,
+    daily: Option<Res<DailyChallenge>>,
+    root: Query<Entity, With<GameoverUIRoot>>,
+    daily_text: Query<Entity, With<DailyChallengeText>>,
+    font_server: Res<FontsAtlas>,
+) {
+    let (Some(daily), Ok(root)) = (daily, root.get_single()) else {
+        return;
+    };
+    if !daily_text.is_empty() {
+        return;
+    }
+
+    let value = if daily.ranked {
+        format!("Daily Challenge {}", daily.date)
+    } else {
+        format!(
+            "Daily Challenge {}\nPractice, only the first attempt of the day is ranked",
+            daily.date
+        )
+    };
+
+    let text = commands
+        .spawn(TextBundle {
+            style: Style {
+                align_self: AlignSelf::Center,
+                margin: UiRect::all(Val::Percent(2.0)),
+                ..default()
+            },
+            text: Text::from_section(
+                value,
+                TextStyle {
+                    font: font_server.common_font.clone(),
+                    font_size: 22.0,
+                    color: Color::BLACK,
+                },
+            )
+            .with_alignment(TextAlignment::CENTER),
+            ..default()
+        })
+        .insert(DailyChallengeText)
+        .id();
+
+    commands.entity(root).add_child(text);
+}
+
+fn leave_daily_challenge(mut commands: Commands) {
+    commands.remove_resource::<DailyChallenge>();
+}
@@ -0,0 +1,177 @@
+use std::error::Error;
+
+use bevy::prelude::*;
+use bevy_renet::renet::RenetServer;
+
+use crate::{
+    archetypes::ArchetypesPlugin,
+    assets::{EntitiesAtlas, FontsAtlas},
+    boss::{recover_stunned_bosses, Segments},
+    cli::ServerArgs,
+    coop::{CoopHostPlugin, CoopMatch, NetworkId},
+    defeat_zone::{gameover, DefeatZone, IglooHit, DEFEAT_ZONE_Y},
+    enemy::{enemy_walk, generate_enemy, spawn_entity, Enemy, GeneratedEnemy},
+    gameplay::RunDifficulty,
+    levels::{Levels, LevelsPlugin},
+    network::{create_server, start_match, MatchMode, ServerLobby},
+    rng::{start_run, GameRng},
+    stage::{stage_progress, StageComponent},
+    words::WordsPlugin,
+    GameMode, GameState,
+};
+
+/// Authoritative game without window, rendering or audio. In co-op the server
+/// simulates the shared field, in versus every player simulates its own field
+/// and the server only mirrors the stages.
+pub struct DedicatedServerPlugin;
+impl Plugin for DedicatedServerPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(EntitiesAtlas::headless())
+            .insert_resource(GameRng::new(None))
+            .init_resource::<RunDifficulty>()
+            .init_resource::<FontsAtlas>()
+            .add_plugin(WordsPlugin)
+            .add_plugin(ArchetypesPlugin)
+            .add_plugin(LevelsPlugin)
+            .add_plugin(CoopHostPlugin)
+            .add_event::<IglooHit>()
+            .add_state(GameState::Lobby)
+            .add_system(reset_when_empty)
+            .add_system_set(SystemSet::on_enter(GameState::Lobby).with_system(clear_field))
+            .add_system_set(SystemSet::on_update(GameState::Lobby).with_system(start_coop))
+            .add_system_set(
+                SystemSet::on_enter(GameState::Gameplay)
+                    .with_system(spawn_field)
+                    .with_system(start_run),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Gameplay)
+                    .with_system(spawn_entity)
+                    .with_system(generate_enemy)
+                    .with_system(enemy_walk)
+                    .with_system(follow_boss_segments)
+                    .with_system(recover_stunned_bosses)
+                    .with_system(stage_progress)
+                    .with_system(despawn_typed_enemies)
+                    .with_system(gameover),
+            );
+    }
+}
+
+/// Start the co-op game once enough players joined, versus games start on
+/// their own.
+fn start_coop(
+    args: Res<ServerArgs>,
+    mut server: ResMut<RenetServer>,
+    mut lobby: ResMut<ServerLobby>,
+    mut state: ResMut<State<GameState>>,
+) {
+    if lobby.mode != MatchMode::Coop || lobby.players.len() < args.players {
+        return;
+    }
+
+    if lobby.can_start() {
+        info!("starting a co-op game with {} players", lobby.players.len());
+        start_match(&mut server, &mut lobby);
+        // `Err` when the last player left in the same frame
+        let _ = state.set(GameState::Gameplay);
+    }
+}
+
+fn spawn_field(mut commands: Commands, lobby: Res<ServerLobby>, levels: Res<Levels>) {
+    // the server is not one of the players
+    commands.insert_resource(CoopMatch::new(0, &lobby.player_infos()));
+
+    commands
+        .spawn(StageComponent::first_level(&levels))
+        .insert(Name::new("Stage"));
+    commands
+        .spawn(TransformBundle::from(Transform::from_xyz(
+            0.0,
+            DEFEAT_ZONE_Y,
+            1.0,
+        )))
+        .insert(DefeatZone);
+}
+
+#[allow(clippy::type_complexity)]
+fn clear_field(
+    mut commands: Commands,
+    field: Query<Entity, Or<(With<Enemy>, With<StageComponent>, With<DefeatZone>)>>,
+) {
+    for entity in field.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<CoopMatch>();
+}
+
+/// Nobody throws fishes here: an enemy is gone as soon as its word is typed
+/// and the server forgot its claim.
+fn despawn_typed_enemies(
+    mut commands: Commands,
+    lobby: Res<ServerLobby>,
+    enemies: Query<(Entity, &NetworkId, Option<&GeneratedEnemy>)>,
+    mut stage: Query<&mut StageComponent>,
+) {
+    for (entity, id, generated) in enemies.iter() {
+        if lobby.claims.contains_key(&id.0) {
+            continue;
+        }
+
+        commands.entity(entity).despawn_recursive();
+        if generated.is_none() {
+            stage.single_mut().enemy_defeated();
+        }
+    }
+}
+
+/// Bosses stand still after each segment typed by the players.
+fn follow_boss_segments(
+    lobby: Res<ServerLobby>,
+    mut bosses: Query<(&NetworkId, &mut Enemy, &mut Segments, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    for (id, mut enemy, mut segments, children) in bosses.iter_mut() {
+        let (Some(claim), Ok(mut text)) = (lobby.claims.get(&id.0), texts.get_mut(children[0]))
+        else {
+            continue;
+        };
+
+        segments.skip_to(&mut enemy, &mut text, claim.remaining.chars().count());
+    }
+}
+
+/// Get ready for the next game once every player left.
+fn reset_when_empty(mut lobby: ResMut<ServerLobby>, mut state: ResMut<State<GameState>>) {
+    if !lobby.started || !lobby.players.is_empty() {
+        return;
+    }
+
+    info!("every player left, waiting for a new game");
+    *lobby = ServerLobby::new(lobby.mode);
+
+    // the lobby wins over a game over queued in the same frame
+    if *state.current() != GameState::Lobby {
+        state.overwrite_set(GameState::Lobby).unwrap();
+    }
+}
+
+/// Resources of a dedicated server listening on `args.public_addr`.
+pub fn setup_server(app: &mut App, args: ServerArgs) -> Result<(), Box<dyn Error>> {
+    let lobby = ServerLobby::new(args.mode);
+    let server = create_server(args.public_addr, lobby.max_players())?;
+
+    info!(
+        "listening on {} for a {:?} game",
+        args.public_addr, args.mode
+    );
+
+    app.insert_resource(server)
+        .insert_resource(lobby)
+        .insert_resource(match args.mode {
+            MatchMode::Versus => GameMode::Versus,
+            MatchMode::Coop => GameMode::CoopHost,
+        })
+        .insert_resource(args);
+    Ok(())
+}
@@ -1,18 +1,48 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 
-use crate::{assets::EntitiesAtlas, enemy::Enemy, GameState, CASE_SIZE, WINDOW_HEIGHT};
+use crate::{
+    archetypes::EnemyArchetypes,
+    assets::EntitiesAtlas,
+    enemy::{Enemy, EnemyKind, GeneratedEnemy},
+    mixer::SoundEffect,
+    request_game_over,
+    stage::StageComponent,
+    GameMode, GameState, CASE_SIZE, WINDOW_HEIGHT,
+};
 
 pub const DEFEAT_ZONE_HEIGHT: f32 = 85.0;
+pub const DEFEAT_ZONE_Y: f32 = -(WINDOW_HEIGHT / 2.0 - DEFEAT_ZONE_HEIGHT / 2.0);
+pub const IGLOO_HIT_FLASH: u64 = 300; // in ms
 
 #[derive(Component)]
 pub struct DefeatZone;
 
+/// The igloo turns red for a moment when an enemy reaches it.
+#[derive(Component)]
+pub struct IglooFlash {
+    timer: Timer,
+}
+
+/// Sent when an enemy reaches the igloo.
+pub struct IglooHit {
+    pub enemy: Entity,
+    pub damage: u32,
+}
+
 pub struct DefeatZonePlugin;
 
 impl Plugin for DefeatZonePlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system_to_stage(StartupStage::PostStartup, spawn_defeat_zone)
-            .add_system_set(SystemSet::on_update(GameState::Gameplay).with_system(gameover));
+        app.add_event::<IglooHit>()
+            .add_startup_system_to_stage(StartupStage::PostStartup, spawn_defeat_zone)
+            .add_system_set(
+                SystemSet::on_update(GameState::Gameplay)
+                    .with_system(gameover)
+                    .with_system(igloo_hit_effect)
+                    .with_system(fade_igloo_flash),
+            );
     }
 }
 
@@ -25,28 +55,80 @@ fn spawn_defeat_zone(mut commands: Commands, atlases: Res<EntitiesAtlas>) {
         .spawn(SpriteSheetBundle {
             sprite,
             texture_atlas: atlases.texture_atlas.clone(),
-            transform: Transform::from_translation(Vec3::new(
-                0.0,
-                -(WINDOW_HEIGHT / 2.0 - DEFEAT_ZONE_HEIGHT / 2.0),
-                1.0,
-            )),
+            transform: Transform::from_translation(Vec3::new(0.0, DEFEAT_ZONE_Y, 1.0)),
             ..default()
         })
         .insert(DefeatZone);
 }
 
-fn gameover(
-    enemies: Query<&Transform, With<Enemy>>,
+/// Enemies reaching the igloo damage it and vanish, the game is over once it
+/// has no health left.
+#[allow(clippy::too_many_arguments)]
+pub fn gameover(
+    mut commands: Commands,
+    enemies: Query<(Entity, &Transform, &EnemyKind, Option<&GeneratedEnemy>), With<Enemy>>,
     defeat_zone: Query<&Transform, (With<DefeatZone>, Without<Enemy>)>,
+    mut stage: Query<&mut StageComponent>,
+    archetypes: Res<EnemyArchetypes>,
+    mut hits: EventWriter<IglooHit>,
     mut state: ResMut<State<GameState>>,
+    mode: Res<GameMode>,
 ) {
     let defeat_zone = defeat_zone.single();
+    let mut stage = stage.single_mut();
+
+    for (entity, transform, kind, generated) in enemies.iter() {
+        let offset = transform.translation.y - defeat_zone.translation.y;
+        if offset.abs() > DEFEAT_ZONE_HEIGHT / 2.0 {
+            continue;
+        }
+
+        let damage = archetypes[kind.0].damage;
+        commands.entity(entity).despawn_recursive();
+        stage.igloo_hit(damage, generated.is_some());
+        hits.send(IglooHit {
+            enemy: entity,
+            damage,
+        });
+    }
+
+    // the co-op host decides when the team is defeated
+    if stage.is_igloo_destroyed() && !mode.is_replica() {
+        request_game_over(&mut state);
+    }
+}
+
+fn igloo_hit_effect(
+    mut commands: Commands,
+    mut hits: EventReader<IglooHit>,
+    mut defeat_zone: Query<(Entity, &mut TextureAtlasSprite), With<DefeatZone>>,
+    mut sounds: EventWriter<SoundEffect>,
+) {
+    if hits.iter().next().is_none() {
+        return;
+    }
+
+    let (entity, mut sprite) = defeat_zone.single_mut();
+    sprite.color = Color::RED;
+    commands.entity(entity).insert(IglooFlash {
+        timer: Timer::new(Duration::from_millis(IGLOO_HIT_FLASH), TimerMode::Once),
+    });
+    sounds.send(SoundEffect::IglooHit);
+}
+
+fn fade_igloo_flash(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut flashes: Query<(Entity, &mut IglooFlash, &mut TextureAtlasSprite)>,
+) {
+    for (entity, mut flash, mut sprite) in flashes.iter_mut() {
+        flash.timer.tick(time.delta());
+        sprite.color =
+            Color::RED * (1.0 - flash.timer.percent()) + Color::WHITE * flash.timer.percent();
 
-    for enemy in enemies.iter() {
-        let offset = enemy.translation.y - defeat_zone.translation.y;
-        if offset.abs() <= DEFEAT_ZONE_HEIGHT / 2.0 {
-            state.set(GameState::GameOver).unwrap();
-            return;
+        if flash.timer.finished() {
+            sprite.color = Color::WHITE;
+            commands.entity(entity).remove::<IglooFlash>();
         }
     }
 }
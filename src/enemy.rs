@@ -1,22 +1,26 @@
 use bevy_inspector_egui::Inspectable;
-use rand::{self, Rng};
-use std::time::Duration;
+use rand::Rng;
+use std::{collections::VecDeque, time::Duration};
 
 use bevy::prelude::*;
 
 use crate::{
+    archetypes::{EnemyArchetypes, PowerUp},
     assets::{EntitiesAtlas, FontsAtlas},
+    boss::{BossHealthText, Segments, SEGMENT_BREAK},
+    gameplay::RunDifficulty,
+    mixer::SoundEffect,
     penguins::{FishThrowed, PenguinIdleAnimated, PENGUIN_THROW_ORIGIN_Y},
-    player::{PlayerCombosChanged, PlayerScore, PlayerStats},
+    player::{PlayerColor, PlayerCombosChanged, PlayerScore, PlayerStats},
+    power_ups::PowerUps,
+    rng::GameRng,
+    rules,
     stage::{PlayerCombosText, StageComponent},
     words::{TextEnemy, WordsResource},
-    GameState, CASE_SIZE, WINDOW_HEIGHT, WINDOW_WIDTH,
+    GameMode, GameState, CASE_SIZE, WINDOW_HEIGHT, WINDOW_WIDTH,
 };
 
 pub const BASE_SPEED: f32 = CASE_SIZE * 0.8 / 60.0; // in px per seconds
-pub const ENEMY_GENERATION_TIME_INTERVAL: (f64, f64) = (4.0, 7.0);
-pub const SPAWN_TIME_INTERVAL: u64 = 2000;
-pub const MIN_SPAWN_TIME_INTERVAL: u64 = 500;
 pub const BLOOD_CLEAR_DELAY: f32 = 4000.0; // in ms
 
 #[derive(Component)]
@@ -33,6 +37,7 @@ pub enum EnemyState {
     #[default]
     Walk,
     Idle,
+    Stunned, // a boss catching its breath between two segments
 }
 
 #[derive(Component, Default, Inspectable, Reflect)]
@@ -44,19 +49,28 @@ pub struct Enemy {
     pub points: u32,
 }
 
+/// Index of the enemy archetype in `EnemyArchetypes`.
+#[derive(Component, Clone, Copy)]
+pub struct EnemyKind(pub usize);
+
 #[derive(Component)]
 pub struct EnemyGenitor {
-    pub enemy_type: EnemyGeneratedType,
+    pub child: usize, // archetype of the generated enemies
+    pub interval: f64,
     pub last_generation: f64,
 }
 
-pub enum EnemyGeneratedType {
-    LittleSpider,
-}
-
 #[derive(Component)]
 pub struct Target;
 
+/// When the game was paused, the enemy generations are delayed by the pause.
+#[derive(Resource)]
+struct PausedAt(f64);
+
+/// Enemy locked by another player of a co-op game, it can't be targeted.
+#[derive(Component)]
+pub struct ClaimedBy;
+
 #[derive(Component)]
 pub struct GeneratedEnemy; // entity generated by other entity
 
@@ -68,11 +82,41 @@ pub struct EnemyAnimated {
 #[derive(Component)]
 pub struct MakeChilds;
 
+/// Sent when the player starts typing the word of an enemy.
+pub struct TargetLocked {
+    pub enemy: Entity,
+}
+
+/// Sent when the player types the next character of its target.
+pub struct EnemyHit {
+    pub enemy: Entity,
+    pub char: char,
+}
+
+/// Sent to defeat an enemy without typing the rest of its word, e.g. by a bomb.
+/// Its points are given like for a typed word.
+pub struct DefeatEnemy {
+    pub enemy: Entity,
+}
+
+/// Sent when a targeted enemy is defeated and its points are given.
+pub struct EnemyKilled {
+    pub enemy: Entity,
+    pub points: u32,
+    pub long_enemy: bool, // the enemy had a long word
+    pub generated: bool,
+    pub power_up: Option<PowerUp>,
+}
+
 pub struct EnemyPlugin;
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
         app.add_system_to_stage(CoreStage::PreUpdate, key_pressed.label("key_pressed"))
             .register_type::<Enemy>()
+            .add_event::<TargetLocked>()
+            .add_event::<EnemyHit>()
+            .add_event::<DefeatEnemy>()
+            .add_event::<EnemyKilled>()
             .add_system_set(
                 SystemSet::on_update(GameState::Gameplay)
                     .with_system(animate_enemy)
@@ -83,6 +127,12 @@ impl Plugin for EnemyPlugin {
                     .with_system(clear_dead_enemy_blood)
                     .with_system(animate_dead_enemy_blood)
                     .with_system(give_point_when_dead.label("give_point_when_dead")),
+            )
+            .add_system_set(
+                SystemSet::on_pause(GameState::Gameplay).with_system(pause_enemy_generation),
+            )
+            .add_system_set(
+                SystemSet::on_resume(GameState::Gameplay).with_system(resume_enemy_generation),
             );
     }
 }
@@ -106,11 +156,20 @@ fn animate_enemy(
     }
 }
 
-fn enemy_walk(time: Res<Time>, mut enemys: Query<(&Enemy, &mut Transform)>) {
+pub fn enemy_walk(
+    time: Res<Time>,
+    power_ups: Option<Res<PowerUps>>,
+    difficulty: Res<RunDifficulty>,
+    mut enemys: Query<(&Enemy, &mut Transform)>,
+) {
+    let speed = BASE_SPEED
+        * difficulty.0.speed_factor()
+        * power_ups.map_or(1.0, |power_ups| power_ups.speed_factor());
+
     for (enemy, mut transform) in enemys.iter_mut() {
         if enemy.state == EnemyState::Walk {
             transform.translation.y -=
-                enemy.velocity * BASE_SPEED * time.delta().as_millis() as f32 / 60.0;
+                enemy.velocity * speed * time.delta().as_millis() as f32 / 60.0;
         }
     }
 }
@@ -119,23 +178,51 @@ fn enemy_walk(time: Res<Time>, mut enemys: Query<(&Enemy, &mut Transform)>) {
 fn key_pressed(
     mut commands: Commands,
     mut inputs: EventReader<ReceivedCharacter>,
-    mut enemy: Query<(Entity, &mut Enemy, &Children, &GlobalTransform), With<Target>>,
-    mut enemies: Query<(Entity, &mut Enemy, &Children, &GlobalTransform), Without<Target>>,
+    mut enemy: Query<
+        (
+            Entity,
+            &mut Enemy,
+            &Children,
+            &GlobalTransform,
+            Option<&mut Segments>,
+        ),
+        With<Target>,
+    >,
+    mut enemies: Query<
+        (
+            Entity,
+            &mut Enemy,
+            &Children,
+            &GlobalTransform,
+            Option<&mut Segments>,
+        ),
+        (Without<Target>, Without<ClaimedBy>),
+    >,
     mut texts: Query<(&Parent, &GlobalTransform, &mut Text), (Without<Target>, With<TextEnemy>)>,
     atlases: Res<EntitiesAtlas>,
     mut stage: Query<&mut StageComponent>,
     mut penguin: Query<&mut PenguinIdleAnimated, Without<Target>>,
     mut player_stats: ResMut<PlayerStats>,
     combos: Query<Entity, (With<PlayerCombosText>, Without<PlayerCombosChanged>)>,
+    player_color: Res<PlayerColor>,
+    mut locked: EventWriter<TargetLocked>,
+    mut hits: EventWriter<EnemyHit>,
+    mut sounds: EventWriter<SoundEffect>,
+    game_state: Res<State<GameState>>,
 ) {
-    if let Some(key) = inputs.iter().next() {
-        let mut target: Option<(Entity, Mut<Enemy>, &Children, &GlobalTransform)> = None;
-        let mut stage = stage.single_mut();
+    // characters typed in the menus or while paused are not for the enemies
+    if *game_state.current() != GameState::Gameplay {
+        inputs.clear();
+        return;
+    }
 
-        let mut miss = |commands: &mut Commands| {
-            stage.decrease_bonus();
-            player_stats.combos_count = 0;
-            player_stats.misses += 1;
+    // `expected` is the key that should have been pressed, if any
+    let mut miss =
+        |commands: &mut Commands, sounds: &mut EventWriter<SoundEffect>, expected: char| {
+            stage.single_mut().decrease_bonus();
+            player_stats.combo.miss();
+            player_stats.record_miss(expected);
+            sounds.send(SoundEffect::Miss);
 
             if !combos.is_empty() {
                 let combos = combos.single();
@@ -143,7 +230,12 @@ fn key_pressed(
             }
         };
 
-        if enemy.is_empty() {
+    // kept for the next keys of the frame, the commands changing the target
+    // are only applied at its end
+    let mut target = enemy.get_single().ok().map(|(entity, ..)| entity);
+
+    for key in inputs.iter().filter(|key| !key.char.is_control()) {
+        if target.is_none() {
             // check target
             let mut sort = texts
                 .iter_mut()
@@ -156,32 +248,38 @@ fn key_pressed(
                     .unwrap_or(std::cmp::Ordering::Less)
             });
 
-            let mut target_found = false;
-
             for (parent, _, text) in sort.iter_mut() {
-                if text.sections[0].value.starts_with(key.char.to_owned()) {
-                    target = Some(enemies.get_mut(parent.get()).unwrap());
-
+                // enemies claimed by another player are skipped
+                if text.sections[0].value.starts_with(key.char.to_owned())
+                    && enemies.contains(parent.get())
+                {
                     text.sections[0].style.font_size *= 1.25;
-                    text.sections[0].style.color = Color::RED;
+                    text.sections[0].style.color = player_color.0;
 
                     commands.entity(parent.get()).insert(Target);
+                    locked.send(TargetLocked {
+                        enemy: parent.get(),
+                    });
 
-                    target_found = true;
+                    target = Some(parent.get());
                     break;
                 }
             }
-            // if no target selected, the player missed. Decrease the bonus
-            if !target_found {
-                miss(&mut commands);
-            }
         }
 
-        if enemy.is_empty() && target.is_none() {
-            return;
-        }
+        // if no target selected, the player missed. Decrease the bonus
+        let Some(enemy_entity) = target else {
+            miss(&mut commands, &mut sounds, key.char);
+            continue;
+        };
 
-        let (enemy_entity, mut enemy, children, _) = target.unwrap_or_else(|| enemy.single_mut());
+        let Ok((_, mut enemy, children, _, segments)) = enemy
+            .get_mut(enemy_entity)
+            .or_else(|_| enemies.get_mut(enemy_entity))
+        else {
+            target = None;
+            continue;
+        };
 
         let (_, transform, mut text) = texts.get_mut(*children.iter().next().unwrap()).unwrap();
 
@@ -190,79 +288,127 @@ fn key_pressed(
         if string.chars().next().unwrap_or(' ') == key.char {
             text.sections[0].value.remove(0);
 
-            let sprite = TextureAtlasSprite::new(atlases.fish);
-
-            commands
-                .spawn(SpriteSheetBundle {
-                    sprite,
-                    texture_atlas: atlases.texture_atlas.clone(),
-                    transform: Transform {
-                        translation: Vec3::new(0.0, PENGUIN_THROW_ORIGIN_Y, 0.5),
-                        ..default()
-                    },
-                    ..default()
-                })
-                .insert(Name::new("Fish"))
-                .insert(FishThrowed {
-                    target: enemy_entity,
-                    target_position: transform.translation().truncate(),
-                });
+            throw_fish(
+                &mut commands,
+                &atlases,
+                enemy_entity,
+                transform.translation().truncate(),
+            );
+            hits.send(EnemyHit {
+                enemy: enemy_entity,
+                char: key.char,
+            });
+            sounds.send(SoundEffect::Hit);
 
             let mut penguin = penguin.single_mut();
             penguin.animate = true;
         } else {
-            enemy.points = (enemy.points as f32 * 0.9f32) as u32;
+            enemy.points = rules::missed_points(enemy.points);
 
-            miss(&mut commands);
+            miss(
+                &mut commands,
+                &mut sounds,
+                string.chars().next().unwrap_or(key.char),
+            );
         }
 
         if text.sections[0].value.is_empty() {
-            commands
-                .entity(enemy_entity)
-                .remove::<EnemyAnimated>()
-                .remove::<Target>();
-            enemy.state = EnemyState::Idle;
+            // a boss stays the target until its last segment
+            let next_segment =
+                segments.is_some_and(|mut segments| segments.advance(&mut enemy, &mut text));
+
+            if !next_segment {
+                commands
+                    .entity(enemy_entity)
+                    .remove::<EnemyAnimated>()
+                    .remove::<Target>();
+                enemy.state = EnemyState::Idle;
+                target = None;
+            }
         }
     }
 }
 
+pub fn throw_fish(
+    commands: &mut Commands,
+    atlases: &EntitiesAtlas,
+    target: Entity,
+    target_position: Vec2,
+) {
+    let sprite = TextureAtlasSprite::new(atlases.fish);
+
+    commands
+        .spawn(SpriteSheetBundle {
+            sprite,
+            texture_atlas: atlases.texture_atlas.clone(),
+            transform: Transform {
+                translation: Vec3::new(0.0, PENGUIN_THROW_ORIGIN_Y, 0.5),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(Name::new("Fish"))
+        .insert(FishThrowed {
+            target,
+            target_position,
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
 fn give_point_when_dead(
     mut commands: Commands,
     target: RemovedComponents<Target>,
-    enemy_query: Query<(&Enemy, Option<&GeneratedEnemy>)>,
+    mut defeated: EventReader<DefeatEnemy>,
+    enemy_query: Query<(&Enemy, Option<&GeneratedEnemy>, &EnemyKind)>,
+    archetypes: Res<EnemyArchetypes>,
     mut stage: Query<&mut StageComponent>,
     mut score: ResMut<PlayerScore>,
     mut player_stats: ResMut<PlayerStats>,
     combos: Query<Entity, With<PlayerCombosText>>,
+    mut killed: EventWriter<EnemyKilled>,
 ) {
-    let Some( target ) = target.iter().next() else {
-        return;
-    };
-
     let combos = combos.single();
-
     let mut stage = stage.single_mut();
-    let (enemy, generated) = enemy_query.get(target).unwrap();
 
-    score.add((enemy.points as f32 * (1.0 + stage.bonus())) as u32);
-    player_stats.combos_count += 1;
+    // the target is lost once its word is typed
+    let defeated = target
+        .iter()
+        .chain(defeated.iter().map(|defeated| defeated.enemy));
 
-    commands.entity(combos).insert(PlayerCombosChanged);
+    for target in defeated {
+        let Ok((enemy, generated, kind)) = enemy_query.get(target) else {
+            continue;
+        };
 
-    println!(
-        "enemy.rs:241 | Combos: {} ; Misses: {}",
-        player_stats.combos_count, player_stats.misses
-    );
+        // the target was lost to another player before the word was finished
+        if enemy.state != EnemyState::Idle {
+            continue;
+        }
+
+        let points = rules::defeat_points(enemy.points, stage.bonus());
+        score.add(points);
+        player_stats.combo.enemy_defeated();
+
+        commands.entity(combos).insert(PlayerCombosChanged);
+
+        stage.increase_bonus();
+        // Only non generated entity descrease the counter
+        if generated.is_none() {
+            stage.enemy_defeated();
+        }
 
-    stage.increase_bonus();
-    // Only non generated entity descrease the counter
-    if generated.is_none() {
-        stage.enemy_defeated();
+        killed.send(EnemyKilled {
+            enemy: target,
+            points,
+            long_enemy: archetypes[kind.0].is_long(),
+            generated: generated.is_some(),
+            power_up: archetypes[kind.0].power_up,
+        });
     }
 }
 
 #[allow(clippy::too_many_arguments)]
-fn spawn_enemy(
+pub fn spawn_enemy(
     commands: &mut Commands,
 
     elapsed_time: f64,
@@ -271,20 +417,33 @@ fn spawn_enemy(
     points: u32,
     word: impl Into<String>,
 
-    texture: Handle<TextureAtlas>,
+    kind: EnemyKind,
+    archetypes: &EnemyArchetypes,
     fonts: &Res<FontsAtlas>,
-    long_word: bool,
-) {
+) -> Entity {
     let word = word.into();
+    let archetype = &archetypes[kind.0];
+
+    // bosses get their segments one after the other
+    let mut split = word.split(SEGMENT_BREAK).map(String::from);
+    let current = split.next().unwrap_or_default();
+    let next = split.collect::<VecDeque<_>>();
+    let segments = (!next.is_empty())
+        .then(|| Segments::new(next, Duration::from_secs_f32(archetype.segment_pause)));
 
     let mut enemy = commands.spawn(SpriteSheetBundle {
-        texture_atlas: texture,
+        sprite: TextureAtlasSprite {
+            color: archetype.color(),
+            ..default()
+        },
+        texture_atlas: archetype.atlas.clone(),
         transform: Transform::from_translation(Vec3::new(position.x, position.y, 1.0)),
         ..default()
     });
     enemy
         .insert(Enemy {
-            life: word.len(),
+            // one fish per character, not per byte
+            life: word.chars().filter(|c| *c != SEGMENT_BREAK).count(),
             state: EnemyState::Walk,
             velocity,
             points,
@@ -292,26 +451,44 @@ fn spawn_enemy(
         .insert(EnemyAnimated {
             timer: Timer::new(Duration::from_millis(100), TimerMode::Repeating),
         })
+        .insert(kind)
         .insert(Name::new("Enemy"));
-    if long_word {
+    if let Some(children) = &archetype.children {
         enemy.insert(EnemyGenitor {
-            enemy_type: EnemyGeneratedType::LittleSpider,
+            child: archetypes.find(&children.archetype).unwrap_or_default(),
+            interval: children.interval,
             last_generation: elapsed_time,
         });
     }
     let enemy_id = enemy.id();
 
-    let entity = commands
-        .spawn(Text2dBundle {
-            text: Text::from_section(
-                word,
+    let text_style = TextStyle {
+        font: fonts.common_font.clone(),
+        font_size: 20.0,
+        color: Color::BLACK,
+    };
+    let text = match &segments {
+        None => {
+            Text::from_section(current, text_style.clone()).with_alignment(TextAlignment::CENTER)
+        }
+        // the upcoming segments are listed below the one being typed
+        Some(segments) => Text::from_sections([
+            TextSection::new(current, text_style.clone()),
+            TextSection::new(
+                segments.upcoming(),
                 TextStyle {
-                    font: fonts.common_font.clone(),
-                    font_size: 20.0,
-                    color: Color::BLACK,
+                    font_size: 16.0,
+                    color: Color::GRAY,
+                    ..text_style.clone()
                 },
-            )
-            .with_alignment(TextAlignment::CENTER),
+            ),
+        ])
+        .with_alignment(TextAlignment::TOP_CENTER),
+    };
+
+    let entity = commands
+        .spawn(Text2dBundle {
+            text,
             transform: Transform::from_translation(Vec3::new(0.0, -CASE_SIZE / 1.8, 1.5)),
             ..default()
         })
@@ -322,170 +499,181 @@ fn spawn_enemy(
         .id();
 
     commands.entity(enemy_id).add_child(entity);
+
+    if let Some(segments) = segments {
+        let health = commands
+            .spawn(Text2dBundle {
+                text: Text::from_section("", text_style).with_alignment(TextAlignment::CENTER),
+                transform: Transform::from_translation(Vec3::new(0.0, CASE_SIZE / 1.8, 1.5)),
+                ..default()
+            })
+            .insert(BossHealthText)
+            .insert(Name::new("Boss Health Text"))
+            .id();
+
+        commands.entity(enemy_id).insert(segments).add_child(health);
+    }
+
+    enemy_id
 }
 
-fn spawn_entity(
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_entity(
     mut commands: Commands,
     mut stage: Query<&mut StageComponent>,
     time: Res<Time>,
-    atlases: Res<EntitiesAtlas>,
+    archetypes: Res<EnemyArchetypes>,
     fonts: Res<FontsAtlas>,
     words: ResMut<WordsResource>,
+    mut rng: ResMut<GameRng>,
+    mode: Res<GameMode>,
+    difficulty: Res<RunDifficulty>,
 ) {
+    if mode.is_replica() {
+        return;
+    }
+
     let mut stage = stage.single_mut();
 
     if !stage.can_spawn_enemy() {
         return;
     }
 
-    stage.spawn_timer.tick(time.delta());
+    stage
+        .spawn_timer
+        .tick(time.delta().mul_f32(difficulty.0.spawn_rate()));
 
-    if stage.spawn_timer.just_finished() {
-        let mut thread_rng = rand::thread_rng();
-        let position_x_percent = thread_rng.gen_range(-0.8..=0.8);
-        let spawn_long_word = if stage.can_spawn_long_enemy() {
-            thread_rng.gen_bool(0.5)
-        } else {
-            false
-        };
+    if !stage.spawn_timer.just_finished() {
+        return;
+    }
 
-        let position = Vec2::new(
-            position_x_percent * WINDOW_WIDTH / 2.0,
-            WINDOW_HEIGHT / 2.0 + CASE_SIZE / 1.75,
-        );
-
-        let velocity;
-        let points;
-        let word;
-        let texture;
-
-        if spawn_long_word {
-            velocity = 0.7 + stage.level as f32 / 10.0;
-            points = 10;
-            word = words.long_word();
-            texture = atlases.big_spider.clone();
-        } else if thread_rng.gen_bool(0.1) && stage.level > 3 {
-            velocity = 0.7 + stage.level as f32 / 10.0;
-            points = 12;
-            texture = atlases.carot.clone();
-
-            spawn_enemy(
-                &mut commands,
-                time.elapsed_seconds_f64(),
-                position,
-                velocity,
-                points,
-                words.special_word(),
-                texture,
-                &fonts,
-                false,
-            );
-            stage.enemy_born(false);
+    let Some(wave) = stage.next_spawn() else {
+        return;
+    };
 
-            return;
-        } else {
-            velocity = 1.0 + stage.level as f32 / 10.0;
-            points = 5;
-            word = words.short_word();
-            texture = atlases.little_spider.clone();
-        }
+    let rng = &mut rng.spawns;
+    let kind = match &wave.archetype {
+        Some(name) => archetypes.find(name),
+        None => archetypes.pick(stage.level, stage.can_spawn_long_enemy(), rng),
+    };
+    // the enemy is planned by the level, the basic one will do
+    let kind = kind.unwrap_or_default();
+    let archetype = &archetypes[kind];
+
+    let (min_x, max_x) = wave.x_range;
+    let position_x_percent = rng.gen_range(min_x..=max_x);
+    let position = Vec2::new(
+        position_x_percent * WINDOW_WIDTH / 2.0,
+        WINDOW_HEIGHT / 2.0 + CASE_SIZE / 1.75,
+    );
 
-        spawn_enemy(
-            &mut commands,
-            time.elapsed_seconds_f64(),
-            position,
-            velocity,
-            points,
-            word,
-            texture,
-            &fonts,
-            spawn_long_word,
-        );
+    spawn_enemy(
+        &mut commands,
+        time.elapsed_seconds_f64(),
+        position,
+        archetype.speed.at_level(stage.level),
+        archetype.points,
+        archetype.word(&words, rng),
+        EnemyKind(kind),
+        &archetypes,
+        &fonts,
+    );
 
-        stage.enemy_born(spawn_long_word);
-    }
+    stage.enemy_born(archetype.is_long());
 }
 
-fn generate_enemy(
+#[allow(clippy::too_many_arguments)]
+pub fn generate_enemy(
     mut commands: Commands,
-    mut stage: Query<&mut StageComponent>,
+    stage: Query<&StageComponent>,
     time: Res<Time>,
     mut enemies: Query<(&Enemy, &mut EnemyGenitor, &GlobalTransform), With<Enemy>>,
     words: ResMut<WordsResource>,
     fonts: Res<FontsAtlas>,
-    atlases: Res<EntitiesAtlas>,
+    archetypes: Res<EnemyArchetypes>,
+    mut rng: ResMut<GameRng>,
+    mode: Res<GameMode>,
 ) {
-    let mut stage = stage.single_mut();
+    if mode.is_replica() {
+        return;
+    }
+
+    let stage = stage.single();
 
     for (enemy, mut genitor, transform) in enemies.iter_mut() {
         let elapsed = time.elapsed_seconds_f64();
 
-        if elapsed - genitor.last_generation >= ENEMY_GENERATION_TIME_INTERVAL.0
-            && enemy.state == EnemyState::Walk
+        if elapsed - genitor.last_generation >= genitor.interval && enemy.state == EnemyState::Walk
         {
-            let velocity = 1.2 + stage.level as f32 / 10.0;
+            let child = &archetypes[genitor.child];
             let position = Vec2::new(
                 transform.translation().x,
                 transform.translation().y - CASE_SIZE,
             );
-            let points = 3;
-            let word = words.short_word();
-            let texture = atlases.little_spider.clone();
-
-            stage.enemy_born(false);
 
-            spawn_enemy(
+            // generated enemies do not count in the stage progression
+            let generated = spawn_enemy(
                 &mut commands,
                 time.elapsed_seconds_f64(),
                 position,
-                velocity,
-                points,
-                word,
-                texture,
+                child.speed.at_level(stage.level),
+                child.points,
+                child.word(&words, &mut rng.others),
+                EnemyKind(genitor.child),
+                &archetypes,
                 &fonts,
-                false,
             );
+            commands.entity(generated).insert(GeneratedEnemy);
             genitor.last_generation = elapsed;
         }
     }
 }
 
+fn pause_enemy_generation(mut commands: Commands, time: Res<Time>) {
+    commands.insert_resource(PausedAt(time.elapsed_seconds_f64()));
+}
+
+fn resume_enemy_generation(
+    mut commands: Commands,
+    time: Res<Time>,
+    paused_at: Option<Res<PausedAt>>,
+    mut genitors: Query<&mut EnemyGenitor>,
+) {
+    let Some(paused_at) = paused_at else {
+        return;
+    };
+
+    // the frame resuming the game is played, it doesn't count in the pause
+    let pause = time.elapsed_seconds_f64() - time.delta_seconds_f64() - paused_at.0;
+    for mut genitor in genitors.iter_mut() {
+        genitor.last_generation += pause;
+    }
+    commands.remove_resource::<PausedAt>();
+}
+
 fn despawn_enemies(
     mut commands: Commands,
-    enemies: Query<(Entity, &Enemy, &GlobalTransform, Option<&EnemyGenitor>)>,
-    atlases: Res<EntitiesAtlas>,
-    assets: Res<AssetServer>,
-    audio: Res<Audio>,
+    enemies: Query<(Entity, &Enemy, &GlobalTransform, &EnemyKind)>,
+    archetypes: Res<EnemyArchetypes>,
+    mut sounds: EventWriter<SoundEffect>,
 ) {
-    for (entity, enemy, transform, big_enemy) in enemies.iter() {
+    for (entity, enemy, transform, kind) in enemies.iter() {
         if enemy.life == 0 {
             commands.entity(entity).despawn_recursive();
             draw_dead_enemy_blood(
                 &mut commands,
                 transform.translation().truncate(),
-                &atlases,
-                big_enemy.is_some(),
+                archetypes[kind.0].blood_atlas.clone(),
             );
-
-            let sound_effect = assets.load("audio/enemy_killed.ogg");
-            audio.play(sound_effect);
+            sounds.send(SoundEffect::EnemyKilled);
         }
     }
 }
 
-fn draw_dead_enemy_blood(
-    commands: &mut Commands,
-    position: Vec2,
-    atlases: &Res<EntitiesAtlas>,
-    big_enemy: bool,
-) {
+fn draw_dead_enemy_blood(commands: &mut Commands, position: Vec2, atlas: Handle<TextureAtlas>) {
     commands
         .spawn(SpriteSheetBundle {
-            texture_atlas: if big_enemy {
-                atlases.big_enemy_blood.clone()
-            } else {
-                atlases.little_enemy_blood.clone()
-            },
+            texture_atlas: atlas,
             transform: Transform {
                 translation: position.extend(0.0),
                 ..default()
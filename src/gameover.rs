@@ -1,20 +1,65 @@
 use bevy::prelude::*;
 
-use crate::{GameState, assets::FontsAtlas};
+use crate::{
+    assets::FontsAtlas,
+    main_menu::spawn_button,
+    replay::{LastReplay, ReplayPlayer, WatchReplayButton},
+    rng::GameRng,
+    GameMode, GameState,
+};
 
 #[derive(Component)]
 pub struct GameoverUIRoot;
 
+#[derive(Component)]
+pub struct PlayAgainButton;
+
+#[derive(Component)]
+pub struct MainMenuButton;
+
 pub struct GameoverPlugin;
 
 impl Plugin for GameoverPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(spawn_game_over));
+        app.add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(spawn_game_over))
+            .add_system_set(
+                SystemSet::on_update(GameState::GameOver)
+                    .with_system(play_again_button_clicked)
+                    .with_system(main_menu_button_clicked),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::GameOver).with_system(despawn_game_over));
     }
 }
 
-fn spawn_game_over(mut commands: Commands, font_server: Res<FontsAtlas>) {
-    commands
+fn spawn_game_over(
+    mut commands: Commands,
+    font_server: Res<FontsAtlas>,
+    mode: Res<GameMode>,
+    rng: Res<GameRng>,
+    last_replay: Option<Res<LastReplay>>,
+) {
+    // multiplayer games are started again from the lobby
+    let play_again_button = (*mode == GameMode::Solo).then(|| {
+        let button = spawn_button(&mut commands, &font_server, "Play again", Color::RED);
+        commands.entity(button).insert(PlayAgainButton).id()
+    });
+
+    // only solo runs are recorded
+    let watch_replay_button = (*mode == GameMode::Solo && last_replay.is_some()).then(|| {
+        let color = Color::rgb(0.9, 0.8, 0.4);
+        let button = spawn_button(&mut commands, &font_server, "Watch replay", color);
+        commands.entity(button).insert(WatchReplayButton).id()
+    });
+
+    let main_menu_button = spawn_button(
+        &mut commands,
+        &font_server,
+        "Main menu",
+        Color::rgb(0.4, 0.4, 0.9),
+    );
+    commands.entity(main_menu_button).insert(MainMenuButton);
+
+    let root = commands
         .spawn(NodeBundle {
             style: Style {
                 size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
@@ -43,5 +88,66 @@ fn spawn_game_over(mut commands: Commands, font_server: Res<FontsAtlas>) {
                 ),
                 ..default()
             });
-        });
+
+            // the run is played again by starting it with this seed
+            if *mode == GameMode::Solo {
+                commands.spawn(TextBundle {
+                    style: Style {
+                        align_self: AlignSelf::Center,
+                        ..default()
+                    },
+                    text: Text::from_section(
+                        format!("Seed {}", rng.seed()),
+                        TextStyle {
+                            font: font_server.common_font.clone(),
+                            font_size: 22.0,
+                            color: Color::BLACK,
+                        },
+                    ),
+                    ..default()
+                });
+            }
+        })
+        .id();
+
+    if let Some(play_again_button) = play_again_button {
+        commands.entity(root).add_child(play_again_button);
+    }
+    if let Some(watch_replay_button) = watch_replay_button {
+        commands.entity(root).add_child(watch_replay_button);
+    }
+    commands.entity(root).add_child(main_menu_button);
+}
+
+/// A new run is played, even after watching a replay.
+fn play_again_button_clicked(
+    mut commands: Commands,
+    interactions: Query<&Interaction, (With<PlayAgainButton>, Changed<Interaction>)>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Clicked) {
+            commands.remove_resource::<ReplayPlayer>();
+            game_state.set(GameState::Gameplay).unwrap();
+            return;
+        }
+    }
+}
+
+fn main_menu_button_clicked(
+    interactions: Query<&Interaction, (With<MainMenuButton>, Changed<Interaction>)>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Clicked) {
+            game_state.set(GameState::MainMenu).unwrap();
+            return;
+        }
+    }
+}
+
+fn despawn_game_over(mut commands: Commands, root: Query<Entity, With<GameoverUIRoot>>) {
+    for entity in root.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
 }
@@ -1,50 +1,68 @@
-use bevy::{audio::AudioSink, prelude::*};
+use bevy::prelude::*;
 
 use crate::{
-    assets::AudioAtlas, defeat_zone::DefeatZonePlugin, enemy::EnemyPlugin, penguins::PenguinPlugin,
-    stage::StagePlugin, words::WordsPlugin, GameState,
+    archetypes::ArchetypesPlugin,
+    boss::BossPlugin,
+    defeat_zone::DefeatZonePlugin,
+    enemy::{Blood, Enemy, EnemyPlugin},
+    levels::{Levels, LevelsPlugin},
+    mixer::MixerPlugin,
+    music::MusicPlugin,
+    penguins::{FishThrowed, PenguinPlugin},
+    player::{PlayerCombosChanged, PlayerScore, PlayerStats},
+    power_ups::PowerUpsPlugin,
+    rules::Difficulty,
+    stage::{PlayerCombosText, StageComponent, StagePlugin},
+    words::WordsPlugin,
+    GameState,
 };
 
+/// Difficulty of the current run.
+#[derive(Resource, Debug, Default)]
+pub struct RunDifficulty(pub Difficulty);
+
 pub struct GameplayPlugin;
 
 impl Plugin for GameplayPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(EnemyPlugin)
+        app.add_plugin(ArchetypesPlugin)
+            .add_plugin(LevelsPlugin)
+            .add_plugin(EnemyPlugin)
+            .add_plugin(BossPlugin)
+            .add_plugin(PowerUpsPlugin)
             .add_plugin(PenguinPlugin)
             .add_plugin(WordsPlugin)
             .add_plugin(StagePlugin)
             .add_plugin(DefeatZonePlugin)
-            .add_system_set(
-                SystemSet::on_enter(GameState::Gameplay).with_system(play_background_music),
-            )
-            .add_system_set(
-                SystemSet::on_exit(GameState::Gameplay).with_system(stop_background_music),
-            );
+            .add_plugin(MixerPlugin)
+            .add_plugin(MusicPlugin)
+            .init_resource::<RunDifficulty>()
+            .add_system_set(SystemSet::on_enter(GameState::Gameplay).with_system(clear_gameplay));
     }
 }
 
-fn play_background_music(
-    assets: Res<AssetServer>,
-    audio: Res<Audio>,
-    mut audio_atlas: ResMut<AudioAtlas>,
-    audio_sink: Res<Assets<AudioSink>>,
+/// Remove what is left of the last game, a new one starts from the first level.
+#[allow(clippy::type_complexity)]
+fn clear_gameplay(
+    mut commands: Commands,
+    field: Query<Entity, Or<(With<Enemy>, With<Blood>, With<FishThrowed>)>>,
+    mut stage: Query<&mut StageComponent>,
+    levels: Res<Levels>,
+    combos_text: Query<Entity, With<PlayerCombosText>>,
+    mut score: ResMut<PlayerScore>,
+    mut player_stats: ResMut<PlayerStats>,
 ) {
-    let music = assets.load("audio/background.ogg");
-    let handle = audio_sink.get_handle(audio.play_with_settings(
-        music,
-        PlaybackSettings {
-            repeat: true,
-            volume: 1.0,
-            speed: 1.0,
-        },
-    ));
-    audio_atlas.music = Some(handle);
-}
+    for entity in field.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    for mut stage in stage.iter_mut() {
+        *stage = StageComponent::first_level(&levels);
+    }
 
-fn stop_background_music(audio_atlas: Res<AudioAtlas>, audio_sink: Res<Assets<AudioSink>>) {
-    if let Some(music) = &audio_atlas.music {
-        if let Some(sink) = audio_sink.get(music) {
-            sink.stop();
-        }
+    *score = PlayerScore(0);
+    *player_stats = PlayerStats::default();
+    for entity in combos_text.iter() {
+        commands.entity(entity).insert(PlayerCombosChanged);
     }
 }
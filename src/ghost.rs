@@ -0,0 +1,279 @@
+use bevy::prelude::*;
+
+use crate::{
+    assets::FontsAtlas,
+    cli::Args,
+    main_menu::MainMenuUIRoot,
+    player::{PlayerScore, PlayerStats},
+    replay::{Progress, Replay, ReplayPlayer},
+    stage::StageComponent,
+    GameMode, GameState, WINDOW_HEIGHT, WINDOW_WIDTH,
+};
+
+/// Run raced by the ghost.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GhostSource {
+    #[default]
+    Off,
+    BestRun,
+    File, // the `--ghost` replay, e.g. of a teammate
+}
+
+/// Previous run raced in solo, its progress shown next to the player's one at
+/// the same play time.
+#[derive(Resource, Default)]
+pub struct Ghost {
+    pub source: GhostSource,
+    pub name: String,
+    pub final_score: u32,
+    progress: Vec<Progress>,
+    next: usize, // next progress to reach
+    pub current: Progress,
+}
+
+/// Ghost choice shown in the main menu.
+#[derive(Component)]
+pub struct GhostText;
+
+#[derive(Component)]
+pub struct GhostPanel;
+
+impl Ghost {
+    /// Race `replay`, `None` when it has no recorded progress.
+    pub fn new(source: GhostSource, replay: Replay) -> Option<Self> {
+        if replay.progress.is_empty() {
+            return None;
+        }
+
+        let name = match source {
+            GhostSource::BestRun => "best run".to_string(),
+            _ if replay.player.is_empty() => "replay".to_string(),
+            _ => replay.player,
+        };
+
+        Some(Self {
+            source,
+            name,
+            final_score: replay.score,
+            progress: replay.progress,
+            ..default()
+        })
+    }
+
+    /// Load the ghost of `source`, `None` when there is no such run.
+    pub fn load(source: GhostSource, args: &Args) -> Option<Self> {
+        let replay = match source {
+            GhostSource::Off => return Some(Self::default()),
+            GhostSource::BestRun => Replay::best()?,
+            GhostSource::File => {
+                let path = args.ghost.as_ref()?;
+                Replay::load(path)
+                    .map_err(|error| error!("{}: {}", path.display(), error))
+                    .ok()?
+            }
+        };
+
+        let ghost = Self::new(source, replay);
+        if ghost.is_none() {
+            warn!("the replay of the {source:?} ghost has no progress to race");
+        }
+        ghost
+    }
+
+    pub fn is_racing(&self) -> bool {
+        !self.progress.is_empty()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.progress.len()
+    }
+
+    fn restart(&mut self) {
+        self.next = 0;
+        self.current = Progress::default();
+    }
+
+    /// Catch up with a player after `time` seconds of play.
+    pub fn advance(&mut self, time: f32) {
+        while let Some(progress) = self.progress.get(self.next) {
+            if progress.time > time {
+                break;
+            }
+            self.current = *progress;
+            self.next += 1;
+        }
+    }
+}
+
+pub struct GhostPlugin;
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Ghost>()
+            .add_system_set(
+                SystemSet::on_update(GameState::MainMenu)
+                    .with_system(spawn_ghost_text)
+                    .with_system(choose_ghost),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::Gameplay).with_system(start_race))
+            .add_system_set(SystemSet::on_update(GameState::Gameplay).with_system(race))
+            .add_system_set(SystemSet::on_exit(GameState::Gameplay).with_system(end_race));
+    }
+}
+
+fn ghost_label(ghost: &Ghost) -> String {
+    match ghost.source {
+        GhostSource::Off => "Ghost: off, Tab to race a previous run".to_string(),
+        _ => format!("Ghost: {} ({} points)", ghost.name, ghost.final_score),
+    }
+}
+
+fn spawn_ghost_text(
+    mut commands: Commands,
+    root: Query<Entity, With<MainMenuUIRoot>>,
+    ghost_text: Query<Entity, With<GhostText>>,
+    font_server: Res<FontsAtlas>,
+    ghost: Res<Ghost>,
+) {
+    let Ok(root) = root.get_single() else {
+        return;
+    };
+    if !ghost_text.is_empty() {
+        return;
+    }
+
+    let text = commands
+        .spawn(TextBundle {
+            style: Style {
+                align_self: AlignSelf::Center,
+                ..default()
+            },
+            text: Text::from_section(
+                ghost_label(&ghost),
+                TextStyle {
+                    font: font_server.common_font.clone(),
+                    font_size: 22.0,
+                    color: Color::BLACK,
+                },
+            ),
+            ..default()
+        })
+        .insert(GhostText)
+        .id();
+
+    commands.entity(root).add_child(text);
+}
+
+/// Tab cycles through the runs to race, the missing ones are skipped.
+fn choose_ghost(
+    keys: Res<Input<KeyCode>>,
+    args: Res<Args>,
+    mut ghost: ResMut<Ghost>,
+    mut ghost_text: Query<&mut Text, With<GhostText>>,
+) {
+    if keys.just_pressed(KeyCode::Tab) {
+        let mut source = ghost.source;
+        *ghost = loop {
+            source = match source {
+                GhostSource::Off => GhostSource::BestRun,
+                GhostSource::BestRun => GhostSource::File,
+                GhostSource::File => GhostSource::Off,
+            };
+
+            if let Some(ghost) = Ghost::load(source, &args) {
+                break ghost;
+            }
+        };
+    }
+
+    let Ok(mut text) = ghost_text.get_single_mut() else {
+        return;
+    };
+    let label = ghost_label(&ghost);
+    if text.sections[0].value != label {
+        text.sections[0].value = label;
+    }
+}
+
+/// Only played solo runs are raced, not the watched replays.
+fn start_race(
+    mut commands: Commands,
+    mut ghost: ResMut<Ghost>,
+    mode: Res<GameMode>,
+    replay: Option<Res<ReplayPlayer>>,
+    fonts: Res<FontsAtlas>,
+) {
+    if !ghost.is_racing() || *mode != GameMode::Solo || replay.is_some() {
+        return;
+    }
+
+    ghost.restart();
+
+    // in the top left corner, away from the stage data
+    let x = -WINDOW_WIDTH / 2.0 + 8.0;
+    let y = WINDOW_HEIGHT / 2.0 - 8.0;
+
+    commands
+        .spawn(Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: fonts.common_font.clone(),
+                    font_size: 16.0,
+                    color: Color::DARK_GRAY,
+                },
+            )
+            .with_alignment(TextAlignment::TOP_LEFT),
+            transform: Transform::from_translation(Vec3::new(x, y, 5.0)),
+            ..default()
+        })
+        .insert(GhostPanel);
+}
+
+fn race(
+    mut ghost: ResMut<Ghost>,
+    mut panel: Query<&mut Text, With<GhostPanel>>,
+    score: Res<PlayerScore>,
+    player_stats: Res<PlayerStats>,
+    stage: Query<&StageComponent>,
+) {
+    let Ok(mut panel) = panel.get_single_mut() else {
+        return;
+    };
+
+    ghost.advance(player_stats.play_time);
+
+    let stage = stage.get_single().map(|stage| stage.level).unwrap_or(1);
+    let delta = score.0 as i64 - ghost.current.score as i64;
+    let delta = match delta {
+        0 => "Even".to_string(),
+        delta if delta > 0 => format!("{delta} points ahead"),
+        delta => format!("{} points behind", delta.abs()),
+    };
+    let finished = if ghost.is_finished() {
+        ", finished"
+    } else {
+        ""
+    };
+
+    let value = format!(
+        "Ghost {}: {}, stage {}, combo {}{}\nYou: {}, stage {}, combo {}\n{}",
+        ghost.name,
+        ghost.current.score,
+        ghost.current.stage.max(1),
+        ghost.current.combo,
+        finished,
+        score.0,
+        stage,
+        player_stats.combo.count,
+        delta,
+    );
+    if panel.sections[0].value != value {
+        panel.sections[0].value = value;
+    }
+}
+
+fn end_race(mut commands: Commands, panel: Query<Entity, With<GhostPanel>>) {
+    for entity in panel.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,437 @@
+use std::{fmt, fs, io, path::PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    assets::FontsAtlas,
+    cli::Args,
+    daily::{DailyChallenge, DailyScores},
+    gameover::GameoverUIRoot,
+    main_menu::spawn_button,
+    player::{PlayerScore, PlayerStats},
+    replay::ReplayPlayer,
+    stage::StageComponent,
+    GameMode, GameState,
+};
+
+pub const CONFIG_DIR_NAME: &str = "penguin_game";
+pub const HIGH_SCORES_FILE: &str = "high_scores.ron";
+pub const MAX_HIGH_SCORES: usize = 10;
+pub const MAX_NAME_LEN: usize = 12;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HighScoreEntry {
+    pub name: String,
+    pub score: u32,
+    pub stage: u16,
+    pub misses: u32,
+    pub max_combo: u32,
+    pub date: String, // YYYY-MM-DD, local time
+}
+
+/// Best runs, highest score first, saved in `path` when there is one.
+#[derive(Resource, Serialize, Deserialize, Debug, Default)]
+pub struct HighScores {
+    entries: Vec<HighScoreEntry>,
+
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub enum HighScoresError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+    Serialize(ron::Error),
+}
+
+impl fmt::Display for HighScoresError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HighScoresError::Io(error) => write!(f, "cannot access the high scores: {error}"),
+            HighScoresError::Parse(error) => write!(f, "invalid high scores file: {error}"),
+            HighScoresError::Serialize(error) => write!(f, "cannot save the high scores: {error}"),
+        }
+    }
+}
+
+/// Run waiting for the player name on the game over screen.
+#[derive(Resource)]
+pub struct PendingHighScore {
+    pub entry: HighScoreEntry,
+    pub saved_rank: Option<usize>,
+    pub daily: bool, // saved in the daily challenge leaderboard
+}
+
+#[derive(Component)]
+pub struct HighScoreNameText;
+
+#[derive(Component)]
+pub struct LeaderboardUIRoot;
+
+#[derive(Component)]
+pub struct LeaderboardBackButton;
+
+/// Directory of the files written by the game, e.g. `~/.config/penguin_game`.
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(CONFIG_DIR_NAME))
+}
+
+impl HighScores {
+    /// Load the table saved in `path`, starting empty when there is none yet.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, HighScoresError> {
+        let path = path.into();
+
+        let mut high_scores = match fs::read_to_string(&path) {
+            Ok(content) => ron::from_str::<Self>(&content).map_err(HighScoresError::Parse)?,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(error) => return Err(HighScoresError::Io(error)),
+        };
+
+        high_scores.sort();
+        high_scores.path = Some(path);
+        Ok(high_scores)
+    }
+
+    /// Load the table of the user config directory. Problems are reported and
+    /// give an empty table, saved nowhere when there is no config directory.
+    pub fn from_config_dir() -> Self {
+        Self::from_config_file(HIGH_SCORES_FILE)
+    }
+
+    /// Load the table `file_name` of the user config directory, see `from_config_dir`.
+    pub fn from_config_file(file_name: &str) -> Self {
+        let Some(dir) = config_dir() else {
+            warn!("no config directory, {file_name} won't be saved");
+            return Self::default();
+        };
+
+        let path = dir.join(file_name);
+        Self::load(&path).unwrap_or_else(|error| {
+            warn!("{}: {}", path.display(), error);
+            Self {
+                path: Some(path),
+                ..default()
+            }
+        })
+    }
+
+    pub fn save(&self) -> Result<(), HighScoresError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(HighScoresError::Io)?;
+        }
+
+        let content = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(HighScoresError::Serialize)?;
+        fs::write(path, content).map_err(HighScoresError::Io)
+    }
+
+    pub fn entries(&self) -> &[HighScoreEntry] {
+        &self.entries
+    }
+
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0
+            && (self.entries.len() < MAX_HIGH_SCORES
+                || self.entries.iter().any(|entry| score > entry.score))
+    }
+
+    /// Insert a run and return its rank from 0, `None` when it didn't make it.
+    pub fn insert(&mut self, entry: HighScoreEntry) -> Option<usize> {
+        if !self.qualifies(entry.score) {
+            return None;
+        }
+
+        // on equal scores the oldest run stays first
+        let rank = self
+            .entries
+            .iter()
+            .position(|other| entry.score > other.score)
+            .unwrap_or(self.entries.len());
+
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_HIGH_SCORES);
+        Some(rank)
+    }
+
+    fn sort(&mut self) {
+        self.entries
+            .sort_by_key(|entry| std::cmp::Reverse(entry.score));
+        self.entries.truncate(MAX_HIGH_SCORES);
+    }
+}
+
+pub struct HighScoresPlugin;
+impl Plugin for HighScoresPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HighScores::from_config_dir())
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(check_high_score))
+            .add_system_set(
+                SystemSet::on_update(GameState::GameOver)
+                    .with_system(spawn_name_entry)
+                    .with_system(enter_name),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::GameOver).with_system(forget_pending_high_score),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::MainMenu).with_system(leaderboard_back_clicked),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::MainMenu).with_system(despawn_leaderboard),
+            );
+    }
+}
+
+/// Only solo runs are ranked, multiplayer scores depend on the other players.
+/// Daily challenges have a leaderboard of their own, replays have none.
+#[allow(clippy::too_many_arguments)]
+fn check_high_score(
+    mut commands: Commands,
+    mode: Res<GameMode>,
+    replay: Option<Res<ReplayPlayer>>,
+    high_scores: Res<HighScores>,
+    daily: Option<Res<DailyChallenge>>,
+    daily_scores: Res<DailyScores>,
+    score: Res<PlayerScore>,
+    player_stats: Res<PlayerStats>,
+    stage: Query<&StageComponent>,
+    args: Res<Args>,
+) {
+    let board = match &daily {
+        Some(daily) if !daily.ranked => return,
+        Some(_) => &daily_scores.scores,
+        None => &*high_scores,
+    };
+
+    if *mode != GameMode::Solo || replay.is_some() || !board.qualifies(score.0) {
+        return;
+    }
+
+    let stage = stage.get_single().map(|stage| stage.level).unwrap_or(1);
+
+    commands.insert_resource(PendingHighScore {
+        entry: HighScoreEntry {
+            name: args.player_name.chars().take(MAX_NAME_LEN).collect(),
+            score: score.0,
+            stage,
+            misses: player_stats.misses,
+            max_combo: player_stats.combo.max,
+            date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+        },
+        saved_rank: None,
+        daily: daily.is_some(),
+    });
+}
+
+fn spawn_name_entry(
+    mut commands: Commands,
+    pending: Option<Res<PendingHighScore>>,
+    root: Query<Entity, With<GameoverUIRoot>>,
+    name_text: Query<Entity, With<HighScoreNameText>>,
+    font_server: Res<FontsAtlas>,
+) {
+    if pending.is_none() || !name_text.is_empty() || root.is_empty() {
+        return;
+    }
+
+    let text = commands
+        .spawn(TextBundle {
+            style: Style {
+                align_self: AlignSelf::Center,
+                margin: UiRect::all(Val::Percent(3.0)),
+                ..default()
+            },
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: font_server.common_font.clone(),
+                    font_size: 34.0,
+                    color: Color::BLACK,
+                },
+            )
+            .with_alignment(TextAlignment::CENTER),
+            ..default()
+        })
+        .insert(HighScoreNameText)
+        .id();
+
+    commands.entity(root.single()).add_child(text);
+}
+
+fn enter_name(
+    mut inputs: EventReader<ReceivedCharacter>,
+    keys: Res<Input<KeyCode>>,
+    pending: Option<ResMut<PendingHighScore>>,
+    mut high_scores: ResMut<HighScores>,
+    mut daily_scores: ResMut<DailyScores>,
+    mut name_text: Query<&mut Text, With<HighScoreNameText>>,
+) {
+    let Some(mut pending) = pending else {
+        return;
+    };
+
+    if pending.saved_rank.is_none() {
+        for input in inputs.iter() {
+            let name = &mut pending.entry.name;
+            if !input.char.is_control() && name.chars().count() < MAX_NAME_LEN {
+                name.push(input.char);
+            }
+        }
+
+        if keys.just_pressed(KeyCode::Back) {
+            pending.entry.name.pop();
+        }
+
+        if keys.just_pressed(KeyCode::Return) && !pending.entry.name.trim().is_empty() {
+            let board = if pending.daily {
+                &mut daily_scores.scores
+            } else {
+                &mut *high_scores
+            };
+            pending.saved_rank = board.insert(pending.entry.clone());
+
+            if let Err(error) = board.save() {
+                error!("{}", error);
+            }
+        }
+    }
+
+    let Ok(mut text) = name_text.get_single_mut() else {
+        return;
+    };
+
+    let value = match pending.saved_rank {
+        Some(rank) => format!("High score #{} saved", rank + 1),
+        None => format!("New high score!\nName: {}_", pending.entry.name),
+    };
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
+
+fn forget_pending_high_score(mut commands: Commands) {
+    commands.remove_resource::<PendingHighScore>();
+}
+
+/// Leaderboards shown over the main menu.
+pub fn spawn_leaderboard(
+    commands: &mut Commands,
+    font_server: &FontsAtlas,
+    high_scores: &HighScores,
+    daily_scores: &HighScores,
+) {
+    let back_button = spawn_button(commands, font_server, "Back", Color::rgb(0.4, 0.4, 0.9));
+    commands.entity(back_button).insert(LeaderboardBackButton);
+
+    let text_style = |font_size| TextStyle {
+        font: font_server.common_font.clone(),
+        font_size,
+        color: Color::BLACK,
+    };
+
+    let lines = |high_scores: &HighScores| {
+        if high_scores.entries().is_empty() {
+            return "No high score yet".to_string();
+        }
+
+        high_scores
+            .entries()
+            .iter()
+            .enumerate()
+            .map(|(rank, entry)| {
+                format!(
+                    "{}. {} {} - stage {}, combo {}, {} misses, {}",
+                    rank + 1,
+                    entry.name,
+                    entry.score,
+                    entry.stage,
+                    entry.max_combo,
+                    entry.misses,
+                    entry.date
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            background_color: BackgroundColor(Color::WHITE),
+            ..default()
+        })
+        .insert(LeaderboardUIRoot)
+        .with_children(|commands| {
+            commands.spawn(TextBundle {
+                style: Style {
+                    align_self: AlignSelf::Center,
+                    margin: UiRect::all(Val::Percent(3.0)),
+                    ..default()
+                },
+                text: Text::from_section("High Scores", text_style(56.0)),
+                ..default()
+            });
+            commands.spawn(TextBundle {
+                style: Style {
+                    align_self: AlignSelf::Center,
+                    margin: UiRect::all(Val::Percent(3.0)),
+                    ..default()
+                },
+                text: Text::from_section(lines(high_scores), text_style(18.0)),
+                ..default()
+            });
+            commands.spawn(TextBundle {
+                style: Style {
+                    align_self: AlignSelf::Center,
+                    margin: UiRect::all(Val::Percent(3.0)),
+                    ..default()
+                },
+                text: Text::from_section("Daily Challenge", text_style(34.0)),
+                ..default()
+            });
+            commands.spawn(TextBundle {
+                style: Style {
+                    align_self: AlignSelf::Center,
+                    margin: UiRect::all(Val::Percent(3.0)),
+                    ..default()
+                },
+                text: Text::from_section(lines(daily_scores), text_style(18.0)),
+                ..default()
+            });
+        })
+        .add_child(back_button);
+}
+
+fn leaderboard_back_clicked(
+    commands: Commands,
+    interactions: Query<&Interaction, (With<LeaderboardBackButton>, Changed<Interaction>)>,
+    leaderboard_root: Query<Entity, With<LeaderboardUIRoot>>,
+) {
+    if interactions
+        .iter()
+        .any(|interaction| matches!(interaction, Interaction::Clicked))
+    {
+        despawn_leaderboard(commands, leaderboard_root);
+    }
+}
+
+fn despawn_leaderboard(
+    mut commands: Commands,
+    leaderboard_root: Query<Entity, With<LeaderboardUIRoot>>,
+) {
+    for entity in leaderboard_root.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,273 @@
+use std::{fmt, fs, io, path::Path};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{archetypes::EnemyArchetypes, assets::asset_path, rules};
+
+pub const LEVELS_FILE: &str = "levels.ron";
+pub const DEFAULT_X_RANGE: (f32, f32) = (-0.8, 0.8);
+pub const DEFAULT_BOSS_DELAY: f32 = 2.0; // in seconds, after the last enemy of the waves
+
+const BUILTIN_LEVELS: &str = include_str!("../assets/levels.ron");
+
+/// Authored levels, then the endless curve once they run out.
+#[derive(Resource, Deserialize, Debug)]
+pub struct Levels {
+    levels: Vec<Level>,
+    endless: EndlessCurve,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Level {
+    pub waves: Vec<Wave>,
+    #[serde(default)]
+    pub boss: Option<String>, // archetype spawned after the waves
+    #[serde(default = "default_boss_delay")]
+    pub boss_delay: f32, // in seconds
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Wave {
+    #[serde(default)]
+    pub archetype: Option<String>, // random by spawn weights when `None`
+    pub count: usize,
+    pub spawn_delay: f32, // in seconds
+    #[serde(default = "default_x_range")]
+    pub x_range: (f32, f32), // from -1.0 on the left edge to 1.0 on the right edge
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct EndlessCurve {
+    pub count_base: f32,
+    pub count_per_level: f32,
+    pub spawn_delay: f32,
+    pub spawn_delay_factor: f32,
+    pub min_spawn_delay: f32,
+    #[serde(default)]
+    pub boss: Option<String>,
+    #[serde(default)]
+    pub boss_every: u16, // the boss ends the levels multiple of this, never when 0
+}
+
+#[derive(Debug)]
+pub enum LevelsError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+    EmptyLevel(u16),
+    InvalidWave { level: u16, wave: usize },
+    UnknownArchetype { level: u16, archetype: String },
+}
+
+impl fmt::Display for LevelsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelsError::Io(error) => write!(f, "cannot read levels file: {error}"),
+            LevelsError::Parse(error) => write!(f, "invalid levels file: {error}"),
+            LevelsError::EmptyLevel(level) => write!(f, "level {level} spawns no enemy"),
+            LevelsError::InvalidWave { level, wave } => {
+                write!(
+                    f,
+                    "level {level}: wave {wave} has an invalid delay or x range"
+                )
+            }
+            LevelsError::UnknownArchetype { level, archetype } => {
+                write!(
+                    f,
+                    "level {level}: unknown enemy {archetype:?}, a random one is used"
+                )
+            }
+        }
+    }
+}
+
+fn default_x_range() -> (f32, f32) {
+    DEFAULT_X_RANGE
+}
+
+fn default_boss_delay() -> f32 {
+    DEFAULT_BOSS_DELAY
+}
+
+impl Level {
+    /// Waves in spawn order, the boss being a last wave of its own.
+    pub fn plan(&self) -> Vec<Wave> {
+        let mut waves = self.waves.clone();
+
+        if let Some(boss) = &self.boss {
+            waves.push(Wave {
+                archetype: Some(boss.clone()),
+                count: 1,
+                spawn_delay: self.boss_delay,
+                x_range: (0.0, 0.0),
+            });
+        }
+
+        waves
+    }
+}
+
+impl EndlessCurve {
+    pub fn level(&self, level: u16) -> Level {
+        let spawn_delay = rules::endless_spawn_delay(
+            self.spawn_delay,
+            self.spawn_delay_factor,
+            self.min_spawn_delay,
+            level,
+        );
+
+        Level {
+            waves: vec![Wave {
+                archetype: None,
+                count: rules::endless_count(self.count_base, self.count_per_level, level),
+                spawn_delay,
+                x_range: DEFAULT_X_RANGE,
+            }],
+            boss: self.boss.clone().filter(|_| self.has_boss(level)),
+            // the boss follows the pace of the wave
+            boss_delay: spawn_delay,
+        }
+    }
+
+    fn has_boss(&self, level: u16) -> bool {
+        self.boss_every > 0 && level.is_multiple_of(self.boss_every)
+    }
+}
+
+impl Levels {
+    /// Load the levels of `path`, relative to the assets folder. Problems are
+    /// reported and give the built-in levels.
+    pub fn from_file(path: impl AsRef<Path>) -> Self {
+        let path = asset_path(path);
+
+        let levels = fs::read_to_string(&path)
+            .map_err(LevelsError::Io)
+            .and_then(|content| Self::parse(&content));
+
+        levels.unwrap_or_else(|error| {
+            warn!("{}: {}", path.display(), error);
+            Self::builtin()
+        })
+    }
+
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN_LEVELS).expect("invalid built-in levels")
+    }
+
+    pub fn parse(content: &str) -> Result<Self, LevelsError> {
+        let levels: Self = ron::from_str(content).map_err(LevelsError::Parse)?;
+
+        for (level, plan) in (1..).zip(levels.levels.iter().map(Level::plan)) {
+            if plan.iter().all(|wave| wave.count == 0) {
+                return Err(LevelsError::EmptyLevel(level));
+            }
+
+            for (index, wave) in plan.iter().enumerate() {
+                let (min_x, max_x) = wave.x_range;
+                if wave.spawn_delay <= 0.0 || min_x > max_x {
+                    return Err(LevelsError::InvalidWave {
+                        level,
+                        wave: index + 1,
+                    });
+                }
+            }
+        }
+
+        Ok(levels)
+    }
+
+    /// Waves of `level`, from 1.
+    pub fn plan(&self, level: u16) -> Vec<Wave> {
+        match self.levels.get(level as usize - 1) {
+            Some(authored) => authored.plan(),
+            None => self.endless.level(level).plan(),
+        }
+    }
+
+    /// Enemies of the authored levels and of the endless boss missing from
+    /// the archetypes.
+    pub fn check(&self, archetypes: &EnemyArchetypes) -> Vec<LevelsError> {
+        let authored = self.levels.len() as u16;
+        // the endless boss is reported on its first level
+        let endless_boss = (authored + 1..)
+            .take(self.endless.boss_every as usize)
+            .find(|level| self.endless.has_boss(*level))
+            .map(|level| (level, self.endless.level(level).plan()));
+
+        (1..)
+            .zip(self.levels.iter().map(Level::plan))
+            .chain(endless_boss)
+            .flat_map(|(level, plan)| {
+                plan.into_iter()
+                    .filter_map(|wave| wave.archetype)
+                    .filter(|archetype| archetypes.find(archetype).is_none())
+                    .map(move |archetype| LevelsError::UnknownArchetype { level, archetype })
+            })
+            .collect()
+    }
+}
+
+pub struct LevelsPlugin;
+impl Plugin for LevelsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Levels::from_file(LEVELS_FILE))
+            .add_startup_system(check_levels);
+    }
+}
+
+fn check_levels(levels: Res<Levels>, archetypes: Res<EnemyArchetypes>) {
+    for error in levels.check(&archetypes) {
+        warn!("{}: {}", LEVELS_FILE, error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENDLESS: &str = "endless: (count_base: 0.0, count_per_level: 1.0, spawn_delay: 2.0, \
+                           spawn_delay_factor: 0.85, min_spawn_delay: 0.5)";
+
+    fn parse(levels: &str) -> Result<Levels, LevelsError> {
+        Levels::parse(&format!("(levels: [{levels}], {ENDLESS})"))
+    }
+
+    #[test]
+    fn a_level_can_be_a_boss_alone() {
+        let levels = parse(r#"(waves: [], boss: Some("boss"))"#).unwrap();
+
+        let plan = levels.plan(1);
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].archetype.as_deref(), Some("boss"));
+        assert_eq!(plan[0].spawn_delay, DEFAULT_BOSS_DELAY);
+    }
+
+    #[test]
+    fn the_boss_has_its_own_delay() {
+        let levels = parse(
+            r#"(waves: [(count: 2, spawn_delay: 1.0)], boss: Some("boss"), boss_delay: 4.0)"#,
+        )
+        .unwrap();
+
+        let plan = levels.plan(1);
+        assert_eq!(plan[0].spawn_delay, 1.0);
+        assert_eq!(plan[1].spawn_delay, 4.0);
+    }
+
+    #[test]
+    fn invalid_boss_delay_is_rejected() {
+        let error = parse(r#"(waves: [], boss: Some("boss"), boss_delay: 0.0)"#);
+
+        assert!(matches!(
+            error,
+            Err(LevelsError::InvalidWave { level: 1, wave: 1 })
+        ));
+    }
+
+    #[test]
+    fn level_without_enemy_is_rejected() {
+        let error = parse("(waves: [(count: 0, spawn_delay: 1.0)])");
+
+        assert!(matches!(error, Err(LevelsError::EmptyLevel(1))));
+    }
+}
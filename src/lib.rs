@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+
+pub mod archetypes;
+pub mod assets;
+pub mod boss;
+pub mod camera;
+pub mod cli;
+pub mod coop;
+pub mod daily;
+pub mod dedicated;
+pub mod defeat_zone;
+pub mod enemy;
+pub mod gameover;
+pub mod gameplay;
+pub mod ghost;
+pub mod high_scores;
+pub mod levels;
+pub mod lobby;
+pub mod main_menu;
+pub mod mixer;
+pub mod music;
+pub mod network;
+pub mod pause;
+pub mod penguins;
+pub mod player;
+pub mod power_ups;
+pub mod replay;
+pub mod rng;
+pub mod rules;
+pub mod settings;
+pub mod sim;
+pub mod stage;
+pub mod stats;
+pub mod versus;
+pub mod words;
+
+// logical size of the playfield, and of the window at startup. The camera
+// scales the playfield to the actual window, see `camera`
+pub const WINDOW_WIDTH: f32 = 512.0;
+pub const WINDOW_HEIGHT: f32 = 800.0;
+pub const CASE_SIZE: f32 = 64.0;
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum GameState {
+    MainMenu,
+    Lobby, // waiting for the other players of a multiplayer game
+    Gameplay,
+    Paused, // pushed over a solo game, which stays in the background
+    GameOver,
+}
+
+/// End the game once. The defeat conditions may all be met in the same frame,
+/// the first one to request the game over wins.
+pub fn request_game_over(state: &mut State<GameState>) {
+    if *state.current() == GameState::Gameplay {
+        // `Err` when another system already queued it this frame
+        let _ = state.set(GameState::GameOver);
+    }
+}
+
+#[derive(Resource, Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum GameMode {
+    #[default]
+    Solo,
+    Versus,
+    CoopHost,  // simulates the shared field and shares it with the other players
+    CoopGuest, // replicates the field of the co-op host
+}
+
+impl GameMode {
+    /// The field is driven by another game: no spawn and no local defeat.
+    pub fn is_replica(&self) -> bool {
+        *self == GameMode::CoopGuest
+    }
+
+    /// Every player plays on a field of its own, not shared with the others.
+    pub fn has_own_field(&self) -> bool {
+        matches!(self, GameMode::Solo | GameMode::Versus)
+    }
+
+    /// The stage levels up on its own instead of waiting for the server.
+    pub fn levels_up_locally(&self) -> bool {
+        matches!(self, GameMode::Solo | GameMode::CoopHost)
+    }
+}
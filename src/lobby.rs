@@ -0,0 +1,321 @@
+use std::error::Error;
+
+use bevy::prelude::*;
+use bevy_renet::renet::{RenetClient, RenetServer};
+
+use crate::{
+    assets::FontsAtlas,
+    cli::Args,
+    coop::CoopMatch,
+    main_menu::spawn_button,
+    network::{
+        client_running, create_client, create_server, decode, start_match, MatchMode, ServerLobby,
+        ServerMessage, PLAYER_COLORS, RELIABLE_CHANNEL, UNRELIABLE_CHANNEL,
+    },
+    player::PlayerColor,
+    versus::VersusMatch,
+    GameMode, GameState,
+};
+
+#[derive(Component)]
+pub struct LobbyUIRoot;
+
+#[derive(Component)]
+pub struct LobbyPlayersText;
+
+#[derive(Component)]
+pub struct StartMatchButton; // only shown to the co-op host
+
+#[derive(Component)]
+pub struct CancelButton;
+
+/// Sent every frame once the connection to the server is lost.
+pub struct ConnectionLost;
+
+pub struct LobbyPlugin;
+impl Plugin for LobbyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ServerMessage>()
+            .add_event::<ConnectionLost>()
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(client_running)
+                    .with_system(client_receive),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::MainMenu).with_system(leave_multiplayer))
+            .add_system_set(SystemSet::on_enter(GameState::Lobby).with_system(spawn_lobby))
+            .add_system_set(
+                SystemSet::on_update(GameState::Lobby)
+                    .with_system(update_lobby)
+                    .with_system(start_match_button_clicked)
+                    .with_system(cancel_button_clicked)
+                    .with_system(leave_lost_lobby)
+                    .with_system(start_game),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::Lobby).with_system(despawn_lobby));
+    }
+}
+
+/// Host a multiplayer game on `args.server_addr` and join it.
+pub fn host(commands: &mut Commands, args: &Args, mode: MatchMode) -> Result<(), Box<dyn Error>> {
+    let lobby = ServerLobby::new(mode);
+    let server = create_server(args.server_addr, lobby.max_players())?;
+    let client = create_client(args.server_addr, &args.player_name)?;
+
+    commands.insert_resource(server);
+    commands.insert_resource(lobby);
+    commands.insert_resource(client);
+    Ok(())
+}
+
+/// Join the multiplayer game hosted on `args.server_addr`.
+pub fn join(commands: &mut Commands, args: &Args) -> Result<(), Box<dyn Error>> {
+    let client = create_client(args.server_addr, &args.player_name)?;
+
+    commands.insert_resource(client);
+    Ok(())
+}
+
+fn client_receive(
+    mut client: ResMut<RenetClient>,
+    mut messages: EventWriter<ServerMessage>,
+    mut lost: EventWriter<ConnectionLost>,
+) {
+    for channel in [RELIABLE_CHANNEL, UNRELIABLE_CHANNEL] {
+        while let Some(bytes) = client.receive_message(channel) {
+            match decode::<ServerMessage>(&bytes) {
+                Some(message) => messages.send(message),
+                None => warn!("invalid message from the server"),
+            }
+        }
+    }
+
+    if client.disconnected().is_some() {
+        lost.send(ConnectionLost);
+    }
+}
+
+fn spawn_lobby(mut commands: Commands, font_server: Res<FontsAtlas>, args: Res<Args>) {
+    let start_button = spawn_button(&mut commands, &font_server, "Start", Color::RED);
+    commands
+        .entity(start_button)
+        .insert(StartMatchButton)
+        .insert(Visibility { is_visible: false });
+
+    let cancel_button = spawn_button(
+        &mut commands,
+        &font_server,
+        "Cancel",
+        Color::rgb(0.4, 0.4, 0.9),
+    );
+    commands.entity(cancel_button).insert(CancelButton);
+
+    let text_style = TextStyle {
+        font: font_server.common_font.clone(),
+        font_size: 34.0,
+        color: Color::BLACK,
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            background_color: BackgroundColor(Color::WHITE),
+            ..default()
+        })
+        .insert(LobbyUIRoot)
+        .with_children(|commands| {
+            commands.spawn(TextBundle {
+                style: Style {
+                    align_self: AlignSelf::Center,
+                    margin: UiRect::all(Val::Percent(3.0)),
+                    ..default()
+                },
+                text: Text::from_section(
+                    format!("Waiting for players\non {}", args.server_addr),
+                    text_style.clone(),
+                )
+                .with_alignment(TextAlignment::CENTER),
+                ..default()
+            });
+            commands
+                .spawn(TextBundle {
+                    style: Style {
+                        align_self: AlignSelf::Center,
+                        margin: UiRect::all(Val::Percent(3.0)),
+                        ..default()
+                    },
+                    text: Text::from_section("", text_style).with_alignment(TextAlignment::CENTER),
+                    ..default()
+                })
+                .insert(LobbyPlayersText);
+        })
+        .add_child(start_button)
+        .add_child(cancel_button);
+}
+
+fn despawn_lobby(mut commands: Commands, lobby_root: Query<Entity, With<LobbyUIRoot>>) {
+    for entity in lobby_root.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn update_lobby(
+    mut messages: EventReader<ServerMessage>,
+    mut players_text: Query<&mut Text, With<LobbyPlayersText>>,
+    mut start_button: Query<&mut Visibility, With<StartMatchButton>>,
+    server_lobby: Option<Res<ServerLobby>>,
+    font_server: Res<FontsAtlas>,
+) {
+    for message in messages.iter() {
+        let ServerMessage::Lobby { mode, players } = message else {
+            continue;
+        };
+
+        let Ok(mut text) = players_text.get_single_mut() else {
+            continue;
+        };
+
+        let title = match mode {
+            MatchMode::Versus => "Versus",
+            MatchMode::Coop => "Co-op",
+        };
+        text.sections = vec![TextSection::new(
+            format!("{title} - {} players", players.len()),
+            TextStyle {
+                font: font_server.common_font.clone(),
+                font_size: 30.0,
+                color: Color::BLACK,
+            },
+        )];
+        text.sections.extend(players.iter().map(|player| {
+            TextSection::new(
+                format!("\n{}", player.name),
+                TextStyle {
+                    font: font_server.common_font.clone(),
+                    font_size: 30.0,
+                    color: PLAYER_COLORS[player.color],
+                },
+            )
+        }));
+    }
+
+    // the co-op host starts the game once enough players joined
+    if let Ok(mut visibility) = start_button.get_single_mut() {
+        visibility.is_visible =
+            server_lobby.is_some_and(|lobby| lobby.mode == MatchMode::Coop && lobby.can_start());
+    }
+}
+
+fn start_match_button_clicked(
+    interactions: Query<&Interaction, (With<StartMatchButton>, Changed<Interaction>)>,
+    server: Option<ResMut<RenetServer>>,
+    server_lobby: Option<ResMut<ServerLobby>>,
+) {
+    let (Some(mut server), Some(mut server_lobby)) = (server, server_lobby) else {
+        return;
+    };
+
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Clicked) && server_lobby.can_start() {
+            start_match(&mut server, &mut server_lobby);
+        }
+    }
+}
+
+fn cancel_button_clicked(
+    interactions: Query<&Interaction, (With<CancelButton>, Changed<Interaction>)>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Clicked) {
+            // `Err` when the connection was lost in the same frame
+            let _ = game_state.set(GameState::MainMenu);
+            return;
+        }
+    }
+}
+
+/// The server is gone or refused us, e.g. when the lobby is full.
+fn leave_lost_lobby(
+    mut lost: EventReader<ConnectionLost>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    if lost.iter().next().is_some() {
+        warn!("connection to the lobby lost");
+        // `Err` when Cancel was clicked or the game started in the same frame
+        let _ = game_state.set(GameState::MainMenu);
+    }
+}
+
+/// Back in the main menu, any multiplayer game is over.
+fn leave_multiplayer(
+    mut commands: Commands,
+    client: Option<ResMut<RenetClient>>,
+    mut mode: ResMut<GameMode>,
+    mut player_color: ResMut<PlayerColor>,
+) {
+    if let Some(mut client) = client {
+        client.disconnect();
+    }
+
+    commands.remove_resource::<RenetClient>();
+    commands.remove_resource::<RenetServer>();
+    commands.remove_resource::<ServerLobby>();
+    commands.remove_resource::<VersusMatch>();
+    commands.remove_resource::<CoopMatch>();
+
+    *mode = GameMode::Solo;
+    *player_color = PlayerColor::default();
+}
+
+fn start_game(
+    mut commands: Commands,
+    mut messages: EventReader<ServerMessage>,
+    client: Res<RenetClient>,
+    server: Option<Res<RenetServer>>,
+    mut mode: ResMut<GameMode>,
+    mut player_color: ResMut<PlayerColor>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    for message in messages.iter() {
+        let ServerMessage::StartGame {
+            mode: match_mode,
+            players,
+        } = message
+        else {
+            continue;
+        };
+
+        // `Err` when the lobby is left in the same frame
+        if game_state.set(GameState::Gameplay).is_err() {
+            return;
+        }
+
+        let me = client.client_id();
+        *mode = match match_mode {
+            MatchMode::Versus => GameMode::Versus,
+            MatchMode::Coop if server.is_some() => GameMode::CoopHost,
+            MatchMode::Coop => GameMode::CoopGuest,
+        };
+        *player_color = match match_mode {
+            MatchMode::Versus => PlayerColor::default(),
+            MatchMode::Coop => players
+                .iter()
+                .find(|player| player.id == me)
+                .map(|player| PlayerColor(PLAYER_COLORS[player.color]))
+                .unwrap_or_default(),
+        };
+
+        match match_mode {
+            MatchMode::Versus => commands.insert_resource(VersusMatch::new(me, players)),
+            MatchMode::Coop => commands.insert_resource(CoopMatch::new(me, players)),
+        }
+        return;
+    }
+}
@@ -1,40 +1,41 @@
-use assets::AssetsPlugin;
 use bevy::{app::AppExit, prelude::*};
-use bevy_inspector_egui::WorldInspectorPlugin;
-use camera::GameCameraPlugin;
-use gameover::GameoverPlugin;
-use gameplay::GameplayPlugin;
-use main_menu::MainMenuPlugin;
-use player::{PlayerScore, PlayerStats};
-
-mod assets;
-mod camera;
-mod defeat_zone;
-mod enemy;
-mod gameover;
-mod gameplay;
-mod main_menu;
-mod penguins;
-mod player;
-mod stage;
-mod words;
-
-pub const WINDOW_WIDTH: f32 = 512.0;
-pub const WINDOW_HEIGHT: f32 = 800.0;
-pub const CASE_SIZE: f32 = 64.0;
-
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
-pub enum GameState {
-    MainMenu,
-    Gameplay,
-    GameOver,
-}
+use bevy_inspector_egui::{WorldInspectorParams, WorldInspectorPlugin};
+use penguin_game::{
+    assets::AssetsPlugin,
+    camera::GameCameraPlugin,
+    cli::Args,
+    coop::CoopPlugin,
+    daily::DailyPlugin,
+    gameover::GameoverPlugin,
+    gameplay::GameplayPlugin,
+    ghost::GhostPlugin,
+    high_scores::HighScoresPlugin,
+    lobby::LobbyPlugin,
+    main_menu::MainMenuPlugin,
+    network::NetworkPlugin,
+    pause::PausePlugin,
+    player::{PlayerColor, PlayerScore, PlayerStats},
+    replay::ReplayPlugin,
+    rng::{GameRng, RngPlugin},
+    settings::{Settings, SettingsPlugin},
+    stats::StatsPlugin,
+    versus::VersusPlugin,
+    GameMode, GameState, WINDOW_HEIGHT, WINDOW_WIDTH,
+};
 
 fn main() {
+    let args = Args::from_env();
+    // before the window is created with them
+    let settings = Settings::from_config_dir();
+
     let mut app = App::new();
     app.insert_resource(ClearColor(Color::rgb(0.9, 0.92, 0.94)))
         .insert_resource(PlayerScore(0))
         .insert_resource(PlayerStats::default())
+        .insert_resource(GameRng::new(args.seed))
+        .insert_resource(args)
+        .init_resource::<GameMode>()
+        .init_resource::<PlayerColor>()
         .add_plugins(
             DefaultPlugins
                 .set(WindowPlugin {
@@ -42,32 +43,59 @@ fn main() {
                         width: WINDOW_WIDTH,
                         height: WINDOW_HEIGHT,
                         title: "Penguin typing game".to_owned(),
-                        resizable: false,
+                        scale_factor_override: settings.window_scale,
+                        mode: settings.window_mode(),
                         ..default()
                     },
                     ..default()
                 })
                 .set(ImagePlugin::default_nearest()),
-        );
-
-    if !cfg!(release_assertion) {
-        app.add_plugin(WorldInspectorPlugin::new());
-    }
+        )
+        .insert_resource(WorldInspectorParams {
+            enabled: settings.inspector,
+            ..default()
+        })
+        .add_plugin(WorldInspectorPlugin::new())
+        .insert_resource(settings);
 
     app.add_state(GameState::MainMenu)
         .add_plugin(GameCameraPlugin)
         .add_plugin(AssetsPlugin)
+        .add_plugin(NetworkPlugin)
         // Screen plugins
         .add_plugin(MainMenuPlugin)
+        .add_plugin(RngPlugin)
         .add_plugin(GameplayPlugin)
+        .add_plugin(PausePlugin)
         .add_plugin(GameoverPlugin)
-        .add_system(quit_game); // should spawn another menu for pause the game and quit the game
+        .add_plugin(HighScoresPlugin)
+        .add_plugin(DailyPlugin)
+        .add_plugin(ReplayPlugin)
+        .add_plugin(GhostPlugin)
+        .add_plugin(StatsPlugin)
+        .add_plugin(SettingsPlugin)
+        .add_plugin(LobbyPlugin)
+        .add_plugin(VersusPlugin)
+        .add_plugin(CoopPlugin)
+        .add_system(quit_game);
 
     app.run();
 }
 
-fn quit_game(inputs: Res<Input<KeyCode>>, mut exit: EventWriter<AppExit>) {
-    if inputs.just_released(KeyCode::Escape) {
+/// Escape pauses a solo game instead, see `pause::pause_game`.
+fn quit_game(
+    inputs: Res<Input<KeyCode>>,
+    mode: Res<GameMode>,
+    game_state: Res<State<GameState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let solo_game = *mode == GameMode::Solo
+        && matches!(
+            game_state.current(),
+            GameState::Gameplay | GameState::Paused
+        );
+
+    if inputs.just_released(KeyCode::Escape) && !solo_game {
         exit.send(AppExit);
     }
 }
@@ -1,6 +1,17 @@
 use bevy::{app::AppExit, prelude::*};
 
-use crate::{assets::FontsAtlas, GameState};
+use crate::{
+    assets::FontsAtlas,
+    cli::Args,
+    daily::{DailyChallenge, DailyScores},
+    high_scores::{spawn_leaderboard, HighScores},
+    lobby,
+    network::MatchMode,
+    replay::WatchReplayButton,
+    settings::{spawn_settings_menu, Settings, SettingsButton},
+    words::WordsResource,
+    GameState,
+};
 
 #[derive(Component)]
 pub struct MainMenuUIRoot;
@@ -8,6 +19,18 @@ pub struct MainMenuUIRoot;
 #[derive(Component)]
 pub struct StartButton;
 
+#[derive(Component)]
+pub struct DailyButton;
+
+#[derive(Component)]
+pub struct HostButton(MatchMode);
+
+#[derive(Component)]
+pub struct JoinButton;
+
+#[derive(Component)]
+pub struct HighScoresButton;
+
 #[derive(Component)]
 pub struct QuitButton;
 
@@ -15,31 +38,118 @@ pub struct MainMenuPlugin;
 
 impl Plugin for MainMenuPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system_to_stage(StartupStage::PostStartup, spawn_main_menu)
+        app.add_system_set(SystemSet::on_enter(GameState::MainMenu).with_system(spawn_main_menu))
+            .add_system_set(SystemSet::on_exit(GameState::MainMenu).with_system(despawn_main_menu))
             .add_system_set(
                 SystemSet::on_update(GameState::MainMenu)
                     .with_system(start_button_clicked)
+                    .with_system(daily_button_clicked)
+                    .with_system(host_button_clicked)
+                    .with_system(join_button_clicked)
+                    .with_system(settings_button_clicked)
+                    .with_system(high_scores_button_clicked)
                     .with_system(quit_button_clicked),
             );
     }
 }
 
 fn start_button_clicked(
-    mut commands: Commands,
     interactions: Query<&Interaction, (With<StartButton>, Changed<Interaction>)>,
-    menu_root: Query<Entity, With<MainMenuUIRoot>>,
     mut game_state: ResMut<State<GameState>>,
 ) {
     for interaction in &interactions {
         if matches!(interaction, Interaction::Clicked) {
-            let root_entity = menu_root.single();
-            commands.entity(root_entity).despawn_recursive();
+            game_state.set(GameState::Gameplay).unwrap();
+        }
+    }
+}
 
+fn daily_button_clicked(
+    mut commands: Commands,
+    interactions: Query<&Interaction, (With<DailyButton>, Changed<Interaction>)>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Clicked) {
+            commands.insert_resource(DailyChallenge::today());
             game_state.set(GameState::Gameplay).unwrap();
         }
     }
 }
 
+fn host_button_clicked(
+    mut commands: Commands,
+    interactions: Query<(&Interaction, &HostButton), Changed<Interaction>>,
+    args: Res<Args>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    for (interaction, HostButton(mode)) in &interactions {
+        if matches!(interaction, Interaction::Clicked) {
+            if let Err(error) = lobby::host(&mut commands, &args, *mode) {
+                error!("cannot host a game on {}: {}", args.server_addr, error);
+                return;
+            }
+
+            game_state.set(GameState::Lobby).unwrap();
+        }
+    }
+}
+
+fn join_button_clicked(
+    mut commands: Commands,
+    interactions: Query<&Interaction, (With<JoinButton>, Changed<Interaction>)>,
+    args: Res<Args>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Clicked) {
+            if let Err(error) = lobby::join(&mut commands, &args) {
+                error!("cannot join the game on {}: {}", args.server_addr, error);
+                return;
+            }
+
+            game_state.set(GameState::Lobby).unwrap();
+        }
+    }
+}
+
+fn settings_button_clicked(
+    mut commands: Commands,
+    interactions: Query<&Interaction, (With<SettingsButton>, Changed<Interaction>)>,
+    menu_root: Query<Entity, With<MainMenuUIRoot>>,
+    font_server: Res<FontsAtlas>,
+    settings: Res<Settings>,
+    words: Res<WordsResource>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Clicked) {
+            let settings_root = spawn_settings_menu(&mut commands, &font_server, &settings, &words);
+
+            // despawned along with the main menu
+            commands.entity(menu_root.single()).add_child(settings_root);
+        }
+    }
+}
+
+fn high_scores_button_clicked(
+    mut commands: Commands,
+    interactions: Query<&Interaction, (With<HighScoresButton>, Changed<Interaction>)>,
+    font_server: Res<FontsAtlas>,
+    high_scores: Res<HighScores>,
+    daily_scores: Res<DailyScores>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Clicked) {
+            spawn_leaderboard(
+                &mut commands,
+                &font_server,
+                &high_scores,
+                &daily_scores.scores,
+            );
+        }
+    }
+}
+
 fn quit_button_clicked(
     interactions: Query<&Interaction, (With<QuitButton>, Changed<Interaction>)>,
     mut exit: EventWriter<AppExit>,
@@ -55,6 +165,63 @@ fn spawn_main_menu(mut commands: Commands, font_server: Res<FontsAtlas>) {
     let start_button = spawn_button(&mut commands, &font_server, "Start Game", Color::RED);
     commands.entity(start_button).insert(StartButton);
 
+    let daily_button = spawn_button(&mut commands, &font_server, "Daily Challenge", Color::RED);
+    commands.entity(daily_button).insert(DailyButton);
+
+    let settings_button = spawn_button(
+        &mut commands,
+        &font_server,
+        "Settings",
+        Color::rgb(0.9, 0.8, 0.4),
+    );
+    commands.entity(settings_button).insert(SettingsButton);
+
+    let host_versus_button = spawn_button(
+        &mut commands,
+        &font_server,
+        "Host Versus",
+        Color::rgb(0.5, 0.8, 0.5),
+    );
+    commands
+        .entity(host_versus_button)
+        .insert(HostButton(MatchMode::Versus));
+
+    let host_coop_button = spawn_button(
+        &mut commands,
+        &font_server,
+        "Host Co-op",
+        Color::rgb(0.5, 0.8, 0.5),
+    );
+    commands
+        .entity(host_coop_button)
+        .insert(HostButton(MatchMode::Coop));
+
+    let join_button = spawn_button(
+        &mut commands,
+        &font_server,
+        "Join Game",
+        Color::rgb(0.5, 0.8, 0.5),
+    );
+    commands.entity(join_button).insert(JoinButton);
+
+    let high_scores_button = spawn_button(
+        &mut commands,
+        &font_server,
+        "High Scores",
+        Color::rgb(0.9, 0.8, 0.4),
+    );
+    commands.entity(high_scores_button).insert(HighScoresButton);
+
+    let watch_replay_button = spawn_button(
+        &mut commands,
+        &font_server,
+        "Watch Replay",
+        Color::rgb(0.9, 0.8, 0.4),
+    );
+    commands
+        .entity(watch_replay_button)
+        .insert(WatchReplayButton);
+
     let quit_button = spawn_button(
         &mut commands,
         &font_server,
@@ -94,10 +261,23 @@ fn spawn_main_menu(mut commands: Commands, font_server: Res<FontsAtlas>) {
             });
         })
         .add_child(start_button)
+        .add_child(daily_button)
+        .add_child(settings_button)
+        .add_child(host_versus_button)
+        .add_child(host_coop_button)
+        .add_child(join_button)
+        .add_child(high_scores_button)
+        .add_child(watch_replay_button)
         .add_child(quit_button);
 }
 
-fn spawn_button(
+fn despawn_main_menu(mut commands: Commands, menu_root: Query<Entity, With<MainMenuUIRoot>>) {
+    for entity in menu_root.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub fn spawn_button(
     commands: &mut Commands,
     font_server: &FontsAtlas,
     text: &str,
@@ -106,7 +286,7 @@ fn spawn_button(
     commands
         .spawn(ButtonBundle {
             style: Style {
-                size: Size::new(Val::Percent(45.0), Val::Percent(8.0)),
+                size: Size::new(Val::Percent(45.0), Val::Percent(6.0)),
                 align_self: AlignSelf::Center,
                 justify_content: JustifyContent::Center,
                 margin: UiRect::all(Val::Percent(2.0)),
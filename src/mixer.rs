@@ -0,0 +1,161 @@
+use std::collections::{HashMap, VecDeque};
+
+use bevy::prelude::*;
+
+use crate::{assets::AudioAtlas, boss::Segments, penguins::FishThrowed, GameState};
+
+pub const MAX_SAME_SFX: usize = 3; // identical effects playing together, the next ones are dropped
+pub const SFX_OVERLAP: f64 = 0.15; // in seconds, how long an effect counts as playing
+
+/// Volume of the music bus, from 0.0 to 1.0.
+#[derive(Resource, Debug)]
+pub struct MusicVolume(pub f32);
+
+impl Default for MusicVolume {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// Volume of the sound effects bus, from 0.0 to 1.0.
+#[derive(Resource, Debug)]
+pub struct SfxVolume(pub f32);
+
+impl Default for SfxVolume {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// Sent to play a sound effect on the effects bus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SoundEffect {
+    Hit, // a key matching the next character of the target
+    Miss,
+    FishThrow,
+    EnemyKilled,
+    IglooHit,
+    BossArrival,
+    LevelUp,
+    GameOver,
+}
+
+/// Start times of the effects still playing, see `SFX_OVERLAP`.
+#[derive(Resource, Default)]
+struct PlayingEffects(HashMap<SoundEffect, VecDeque<f64>>);
+
+impl SoundEffect {
+    pub const ALL: [SoundEffect; 8] = [
+        SoundEffect::Hit,
+        SoundEffect::Miss,
+        SoundEffect::FishThrow,
+        SoundEffect::EnemyKilled,
+        SoundEffect::IglooHit,
+        SoundEffect::BossArrival,
+        SoundEffect::LevelUp,
+        SoundEffect::GameOver,
+    ];
+
+    pub fn path(self) -> &'static str {
+        match self {
+            SoundEffect::Hit => "audio/hit.wav",
+            SoundEffect::Miss => "audio/miss.wav",
+            SoundEffect::FishThrow => "audio/fish_throw.wav",
+            SoundEffect::EnemyKilled | SoundEffect::IglooHit => "audio/enemy_killed.ogg",
+            SoundEffect::BossArrival => "audio/boss_arrival.wav",
+            SoundEffect::LevelUp => "audio/level_up.wav",
+            SoundEffect::GameOver => "audio/game_over.wav",
+        }
+    }
+
+    /// Playback of the effect, at full volume of the effects bus.
+    fn settings(self) -> PlaybackSettings {
+        match self {
+            // played on every keystroke, they stay in the background
+            SoundEffect::Hit | SoundEffect::FishThrow => PlaybackSettings::ONCE.with_volume(0.4),
+            // the kill sound, slowed down into a thud
+            SoundEffect::IglooHit => PlaybackSettings::ONCE.with_speed(0.5),
+            _ => PlaybackSettings::ONCE,
+        }
+    }
+}
+
+pub struct MixerPlugin;
+impl Plugin for MixerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MusicVolume>()
+            .init_resource::<SfxVolume>()
+            .init_resource::<PlayingEffects>()
+            .add_event::<SoundEffect>()
+            .add_system(announce_fishes)
+            .add_system(announce_bosses)
+            // after the effects sent during the update
+            .add_system_to_stage(CoreStage::PostUpdate, play_sound_effects)
+            .add_system_set(
+                SystemSet::on_enter(GameState::GameOver).with_system(announce_game_over),
+            );
+    }
+}
+
+/// Play the effects on their bus. An effect already playing `MAX_SAME_SFX`
+/// times is dropped, a bomb killing every enemy at once doesn't clip.
+fn play_sound_effects(
+    mut effects: EventReader<SoundEffect>,
+    mut playing: ResMut<PlayingEffects>,
+    audio: Res<Audio>,
+    audio_atlas: Res<AudioAtlas>,
+    audio_sources: Res<Assets<AudioSource>>,
+    sfx_volume: Res<SfxVolume>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_seconds_f64();
+
+    for effect in effects.iter() {
+        let starts = playing.0.entry(*effect).or_default();
+        while starts
+            .front()
+            .is_some_and(|start| now - start > SFX_OVERLAP)
+        {
+            starts.pop_front();
+        }
+
+        // still loading, or never loaded without audio output
+        let Some(source) = audio_atlas
+            .effects
+            .get(effect)
+            .filter(|source| audio_sources.contains(*source))
+        else {
+            continue;
+        };
+        if starts.len() >= MAX_SAME_SFX || sfx_volume.0 <= 0.0 {
+            continue;
+        }
+        starts.push_back(now);
+
+        let settings = effect.settings();
+        audio.play_with_settings(
+            source.clone(),
+            PlaybackSettings {
+                volume: settings.volume * sfx_volume.0,
+                ..settings
+            },
+        );
+    }
+}
+
+/// Fishes are thrown by the keystrokes, the power-ups and the teammates.
+fn announce_fishes(fishes: Query<(), Added<FishThrowed>>, mut effects: EventWriter<SoundEffect>) {
+    for _ in fishes.iter() {
+        effects.send(SoundEffect::FishThrow);
+    }
+}
+
+fn announce_bosses(bosses: Query<(), Added<Segments>>, mut effects: EventWriter<SoundEffect>) {
+    for _ in bosses.iter() {
+        effects.send(SoundEffect::BossArrival);
+    }
+}
+
+fn announce_game_over(mut effects: EventWriter<SoundEffect>) {
+    effects.send(SoundEffect::GameOver);
+}
@@ -0,0 +1,238 @@
+use bevy::{audio::AudioSink, prelude::*};
+
+use crate::{
+    assets::AudioAtlas,
+    defeat_zone::{DEFEAT_ZONE_HEIGHT, DEFEAT_ZONE_Y},
+    enemy::Enemy,
+    mixer::MusicVolume,
+    stage::StageComponent,
+    GameState,
+};
+
+pub const MUSIC_FADE: f32 = 1.5; // in seconds, of a crossfade
+pub const INTENSITY_RATE: f32 = 0.5; // intensity change per second, the music doesn't jump
+pub const DANGER_DISTANCE: f32 = 250.0; // above the defeat zone, a closer enemy raises the intensity
+pub const CROWD: usize = 8; // enemies alive at full intensity
+pub const HEARTBEAT_TRACK: &str = "audio/heartbeat.wav"; // layer following the intensity
+
+/// Background track of the levels from `first_level` until the next track.
+pub struct MusicTrack {
+    pub first_level: u16,
+    pub path: &'static str,
+    pub heartbeat: f32, // lowest intensity of the heartbeat layer, from 0.0 to 1.0
+}
+
+pub const MUSIC_TRACKS: [MusicTrack; 3] = [
+    MusicTrack {
+        first_level: 1,
+        path: "audio/background.ogg",
+        heartbeat: 0.0,
+    },
+    MusicTrack {
+        first_level: 4,
+        path: "audio/music_drive.wav",
+        heartbeat: 0.2,
+    },
+    MusicTrack {
+        first_level: 8,
+        path: "audio/music_storm.wav",
+        heartbeat: 0.4,
+    },
+];
+
+impl MusicTrack {
+    /// Index in `MUSIC_TRACKS` of the track of `level`.
+    pub fn at_level(level: u16) -> usize {
+        MUSIC_TRACKS
+            .iter()
+            .rposition(|track| track.first_level <= level)
+            .unwrap_or(0)
+    }
+}
+
+/// Intensity asked by the stage, from 0.0 to 1.0. It rises as the closest
+/// enemy nears the defeat zone, or as the enemies alive crowd the field.
+pub fn stage_pressure(closest_distance: Option<f32>, enemies_alive: usize) -> f32 {
+    let danger = closest_distance.map_or(0.0, |distance| {
+        1.0 - (distance / DANGER_DISTANCE).clamp(0.0, 1.0)
+    });
+    let crowd = (enemies_alive as f32 / CROWD as f32).min(1.0);
+
+    danger.max(crowd)
+}
+
+#[derive(PartialEq, Eq)]
+enum Layer {
+    Track,
+    Heartbeat, // its volume follows the intensity
+}
+
+/// A sink of the music, fading in until it is faded out.
+struct Voice {
+    sink: Handle<AudioSink>,
+    layer: Layer,
+    fade: f32, // from 0.0 to 1.0
+    fading_out: bool,
+}
+
+/// Music of the run, on the music bus.
+#[derive(Resource, Default)]
+pub struct MusicPlayer {
+    track: Option<usize>, // index in `MUSIC_TRACKS`
+    intensity: f32,
+    voices: Vec<Voice>,
+}
+
+impl MusicPlayer {
+    pub fn intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    fn play(
+        &mut self,
+        layer: Layer,
+        source: Handle<AudioSource>,
+        audio: &Audio,
+        audio_sink: &Assets<AudioSink>,
+    ) {
+        // silent until the first mix, it fades in from there
+        let sink = audio.play_with_settings(source, PlaybackSettings::LOOP.with_volume(0.0));
+        self.voices.push(Voice {
+            sink: audio_sink.get_handle(sink),
+            layer,
+            fade: 0.0,
+            fading_out: false,
+        });
+    }
+
+    fn fade_out(&mut self, layer: Option<Layer>) {
+        for voice in self.voices.iter_mut() {
+            if layer.as_ref().is_none_or(|layer| voice.layer == *layer) {
+                voice.fading_out = true;
+            }
+        }
+    }
+}
+
+pub struct MusicPlugin;
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MusicPlayer>()
+            .add_system(mix_music)
+            .add_system_set(SystemSet::on_enter(GameState::Gameplay).with_system(start_music))
+            .add_system_set(SystemSet::on_update(GameState::Gameplay).with_system(follow_stage))
+            .add_system_set(SystemSet::on_pause(GameState::Gameplay).with_system(pause_music))
+            .add_system_set(SystemSet::on_resume(GameState::Gameplay).with_system(resume_music))
+            .add_system_set(SystemSet::on_exit(GameState::Gameplay).with_system(end_music));
+    }
+}
+
+/// The music of the last run fades out, the heartbeat of the new one is
+/// silent until the first enemies come.
+fn start_music(
+    mut player: ResMut<MusicPlayer>,
+    audio: Res<Audio>,
+    audio_atlas: Res<AudioAtlas>,
+    audio_sink: Res<Assets<AudioSink>>,
+) {
+    player.fade_out(None);
+    player.track = None;
+    player.intensity = 0.0;
+
+    let heartbeat = audio_atlas.music[HEARTBEAT_TRACK].clone();
+    player.play(Layer::Heartbeat, heartbeat, &audio, &audio_sink);
+}
+
+/// Crossfade to the track of the level, and follow the pressure of the stage.
+fn follow_stage(
+    mut player: ResMut<MusicPlayer>,
+    stage: Query<&StageComponent>,
+    enemies: Query<&GlobalTransform, With<Enemy>>,
+    audio: Res<Audio>,
+    audio_atlas: Res<AudioAtlas>,
+    audio_sink: Res<Assets<AudioSink>>,
+    time: Res<Time>,
+) {
+    let Ok(stage) = stage.get_single() else {
+        return;
+    };
+
+    let track = MusicTrack::at_level(stage.level);
+    if player.track != Some(track) {
+        let music = audio_atlas.music[MUSIC_TRACKS[track].path].clone();
+        player.fade_out(Some(Layer::Track));
+        player.play(Layer::Track, music, &audio, &audio_sink);
+        player.track = Some(track);
+    }
+
+    let defeat_zone_top = DEFEAT_ZONE_Y + DEFEAT_ZONE_HEIGHT / 2.0;
+    let closest_distance = enemies
+        .iter()
+        .map(|transform| transform.translation().y - defeat_zone_top)
+        .reduce(f32::min);
+    let intensity =
+        stage_pressure(closest_distance, stage.enemies_alive).max(MUSIC_TRACKS[track].heartbeat);
+
+    let step = INTENSITY_RATE * time.delta_seconds();
+    player.intensity += (intensity - player.intensity).clamp(-step, step);
+}
+
+/// Fade the voices and set their volume on the music bus. The faded out ones
+/// are stopped.
+fn mix_music(
+    mut player: ResMut<MusicPlayer>,
+    music_volume: Res<MusicVolume>,
+    audio_sink: Res<Assets<AudioSink>>,
+    time: Res<Time>,
+) {
+    let step = time.delta_seconds() / MUSIC_FADE;
+    let intensity = player.intensity;
+
+    player.voices.retain_mut(|voice| {
+        voice.fade = if voice.fading_out {
+            (voice.fade - step).max(0.0)
+        } else {
+            (voice.fade + step).min(1.0)
+        };
+
+        // not played yet, or without audio output
+        let Some(sink) = audio_sink.get(&voice.sink) else {
+            return !voice.fading_out || voice.fade > 0.0;
+        };
+
+        if voice.fading_out && voice.fade <= 0.0 {
+            sink.stop();
+            return false;
+        }
+
+        let layer_volume = match voice.layer {
+            Layer::Track => 1.0,
+            Layer::Heartbeat => intensity,
+        };
+        sink.set_volume(music_volume.0 * voice.fade * layer_volume);
+        true
+    });
+}
+
+fn pause_music(player: Res<MusicPlayer>, audio_sink: Res<Assets<AudioSink>>) {
+    for voice in player.voices.iter() {
+        if let Some(sink) = audio_sink.get(&voice.sink) {
+            sink.pause();
+        }
+    }
+}
+
+fn resume_music(player: Res<MusicPlayer>, audio_sink: Res<Assets<AudioSink>>) {
+    for voice in player.voices.iter() {
+        if let Some(sink) = audio_sink.get(&voice.sink) {
+            sink.play();
+        }
+    }
+}
+
+/// The music fades out under the game over sound, or on the way back to the
+/// menus.
+fn end_music(mut player: ResMut<MusicPlayer>) {
+    player.fade_out(None);
+    player.track = None;
+}
@@ -0,0 +1,709 @@
+use std::{
+    collections::HashMap,
+    net::{SocketAddr, UdpSocket},
+    time::SystemTime,
+};
+
+use bevy::{ecs::schedule::ShouldRun, prelude::*};
+use bevy_renet::{
+    renet::{
+        ClientAuthentication, RenetClient, RenetConnectionConfig, RenetError, RenetServer,
+        ServerAuthentication, ServerConfig, ServerEvent, NETCODE_USER_DATA_BYTES,
+    },
+    RenetClientPlugin, RenetServerPlugin,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    archetypes::EnemyArchetypes, levels::Levels, rules, stage::StageComponent, versus::SENT_ENEMY,
+};
+
+pub const PROTOCOL_ID: u64 = 0x5045_4e47_5549_4e00;
+pub const RELIABLE_CHANNEL: u8 = 0;
+pub const UNRELIABLE_CHANNEL: u8 = 1;
+pub const VERSUS_PLAYERS: usize = 2;
+pub const COOP_MIN_PLAYERS: usize = 2;
+pub const COOP_MAX_PLAYERS: usize = 4;
+pub const SPIDERS_PER_LONG_WORD: u32 = 3;
+
+/// Word color of each co-op player, indexed by `PlayerInfo::color`.
+pub const PLAYER_COLORS: [Color; COOP_MAX_PLAYERS] = [
+    Color::RED,
+    Color::rgb(0.1, 0.4, 0.9),
+    Color::rgb(0.1, 0.6, 0.2),
+    Color::rgb(0.8, 0.4, 0.0),
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchMode {
+    Versus,
+    Coop,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PlayerInfo {
+    pub id: u64,
+    pub name: String,
+    pub color: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PlayerScoreInfo {
+    pub id: u64,
+    pub score: u32,
+    pub combos: u32,
+}
+
+/// Enemy spawned by the co-op host and replicated by the other players.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SpawnedEnemy {
+    pub id: u32,
+    pub word: String,
+    pub position: (f32, f32),
+    pub velocity: f32,
+    pub points: u32,
+    pub archetype: String, // name in the enemies file
+    pub long_enemy: bool,
+    pub generated: bool,
+}
+
+/// Messages sent by a player to the server.
+#[derive(Serialize, Deserialize, Debug)]
+pub enum ClientMessage {
+    // versus, every player reports the enemies of its own field by an id of
+    // its choice, the server keeps the count and gives the points
+    EnemyBorn {
+        enemy: u32,
+        archetype: String, // name in the enemies file
+        generated: bool,
+    },
+    EnemyDefeated {
+        enemy: u32,
+    },
+    EnemyReachedIgloo {
+        enemy: u32,
+    },
+    Defeated, // an enemy reached the player's defeat zone
+
+    // co-op, every player types the enemies of the shared field
+    Claim {
+        enemy: u32,
+    },
+    Typed {
+        enemy: u32,
+        char: char,
+    },
+    Score {
+        score: u32,
+        combos: u32,
+    },
+}
+
+/// Messages sent by the server to the players.
+#[derive(Serialize, Deserialize, Debug)]
+pub enum ServerMessage {
+    Lobby {
+        mode: MatchMode,
+        players: Vec<PlayerInfo>,
+    },
+    StartGame {
+        mode: MatchMode,
+        players: Vec<PlayerInfo>,
+    },
+    LevelUp {
+        level: u16,
+    },
+
+    // versus
+    SendSpiders {
+        count: u32,
+    },
+    GameOver {
+        winner_id: u64,
+        winner: String,
+    },
+
+    // co-op
+    EnemySpawned(SpawnedEnemy),
+    Claimed {
+        enemy: u32,
+        owner: Option<u64>, // `None` when the enemy is free again
+        remaining: String,
+    },
+    Typed {
+        enemy: u32,
+        player: u64,
+    },
+    Positions(Vec<(u32, (f32, f32))>), // sent on the unreliable channel
+    Scoreboard(Vec<PlayerScoreInfo>),
+    TeamDefeated,
+}
+
+/// Server side view of a connected player. In versus, the stage is the
+/// authoritative copy of the one played on the client.
+pub struct ServerPlayer {
+    pub name: String,
+    pub color: usize, // index in `PLAYER_COLORS`
+    pub stage: StageComponent,
+    pub score: u32,
+    pub combos: u32,
+    pub enemies: HashMap<u32, FieldEnemy>, // versus, alive on the player's field
+    pub spiders_sent: u32,                 // versus, sent by the opponent and not spawned yet
+}
+
+/// A versus enemy reported by the player of the field.
+pub struct FieldEnemy {
+    pub kind: usize, // index in `EnemyArchetypes`
+    pub generated: bool,
+}
+
+impl ServerPlayer {
+    fn new(name: String, color: usize, levels: &Levels) -> Self {
+        Self {
+            name,
+            color,
+            stage: StageComponent::first_level(levels),
+            score: 0,
+            combos: 0,
+            enemies: HashMap::new(),
+            spiders_sent: 0,
+        }
+    }
+
+    /// Whether the client could spawn an enemy of `kind`. The stage plans the
+    /// spawned ones, the generated ones come from an enemy of the field or are
+    /// the spiders sent by the opponent.
+    fn accept_spawn(&mut self, kind: usize, generated: bool, archetypes: &EnemyArchetypes) -> bool {
+        let archetype = &archetypes[kind];
+
+        if generated {
+            let sent_spider = archetypes.find(SENT_ENEMY).unwrap_or_default();
+            if kind == sent_spider && self.spiders_sent > 0 {
+                self.spiders_sent -= 1;
+                return true;
+            }
+
+            return self.enemies.values().any(|enemy| {
+                archetypes[enemy.kind]
+                    .children
+                    .as_ref()
+                    .is_some_and(|children| children.archetype == archetype.name)
+            });
+        }
+
+        // the wave is consumed like on the client, which spawns in the same order
+        let long_allowed = self.stage.can_spawn_long_enemy();
+        if !self.stage.can_spawn_enemy() {
+            return false;
+        }
+        let Some(wave) = self.stage.next_spawn() else {
+            return false;
+        };
+
+        match wave.archetype {
+            Some(name) => archetypes.find(&name).unwrap_or_default() == kind,
+            None => {
+                archetype.spawn_weight(self.stage.level) > 0.0
+                    && (long_allowed || !archetype.is_long())
+            }
+        }
+    }
+}
+
+/// A co-op enemy as seen by the server: who types it and what is left to type.
+pub struct EnemyClaim {
+    pub owner: Option<u64>,
+    pub remaining: String,
+}
+
+#[derive(Resource)]
+pub struct ServerLobby {
+    pub mode: MatchMode,
+    pub players: HashMap<u64, ServerPlayer>,
+    pub claims: HashMap<u32, EnemyClaim>,
+    pub started: bool,
+    pub finished: bool,
+}
+
+impl ServerLobby {
+    pub fn new(mode: MatchMode) -> Self {
+        Self {
+            mode,
+            players: HashMap::new(),
+            claims: HashMap::new(),
+            started: false,
+            finished: false,
+        }
+    }
+
+    pub fn max_players(&self) -> usize {
+        match self.mode {
+            MatchMode::Versus => VERSUS_PLAYERS,
+            MatchMode::Coop => COOP_MAX_PLAYERS,
+        }
+    }
+
+    pub fn can_start(&self) -> bool {
+        let players = self.players.len();
+
+        !self.started
+            && match self.mode {
+                MatchMode::Versus => players == VERSUS_PLAYERS,
+                MatchMode::Coop => (COOP_MIN_PLAYERS..=COOP_MAX_PLAYERS).contains(&players),
+            }
+    }
+
+    /// Players in joining order, which is also the order of their colors.
+    pub fn player_infos(&self) -> Vec<PlayerInfo> {
+        let mut players = self
+            .players
+            .iter()
+            .map(|(id, player)| PlayerInfo {
+                id: *id,
+                name: player.name.clone(),
+                color: player.color,
+            })
+            .collect::<Vec<_>>();
+        players.sort_by_key(|player| player.color);
+        players
+    }
+
+    fn free_color(&self) -> usize {
+        (0..COOP_MAX_PLAYERS)
+            .find(|color| self.players.values().all(|player| player.color != *color))
+            .unwrap_or_default()
+    }
+}
+
+pub struct NetworkPlugin;
+impl Plugin for NetworkPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(RenetServerPlugin::default())
+            .add_plugin(RenetClientPlugin::default())
+            .add_system(log_network_errors)
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(server_running)
+                    .with_system(server_connections.before(server_receive))
+                    .with_system(server_receive),
+            );
+    }
+}
+
+pub fn server_running(server: Option<Res<RenetServer>>) -> ShouldRun {
+    match server {
+        Some(_) => ShouldRun::Yes,
+        None => ShouldRun::No,
+    }
+}
+
+pub fn client_running(client: Option<Res<RenetClient>>) -> ShouldRun {
+    match client {
+        Some(_) => ShouldRun::Yes,
+        None => ShouldRun::No,
+    }
+}
+
+pub fn encode<T: Serialize>(message: &T) -> Vec<u8> {
+    bincode::serialize(message).expect("network messages are always serializable")
+}
+
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Option<T> {
+    bincode::deserialize(bytes).ok()
+}
+
+/// Start a server listening on every interface, on the port of `public_addr`.
+pub fn create_server(
+    public_addr: SocketAddr,
+    max_clients: usize,
+) -> Result<RenetServer, Box<dyn std::error::Error>> {
+    let socket = UdpSocket::bind(SocketAddr::new([0, 0, 0, 0].into(), public_addr.port()))?;
+    let current_time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?;
+
+    Ok(RenetServer::new(
+        current_time,
+        ServerConfig::new(
+            max_clients,
+            PROTOCOL_ID,
+            public_addr,
+            ServerAuthentication::Unsecure,
+        ),
+        RenetConnectionConfig::default(),
+        socket,
+    )?)
+}
+
+pub fn create_client(
+    server_addr: SocketAddr,
+    player_name: &str,
+) -> Result<RenetClient, Box<dyn std::error::Error>> {
+    let socket = UdpSocket::bind(SocketAddr::new([0, 0, 0, 0].into(), 0))?;
+    let current_time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?;
+    // players joining in the same millisecond still get their own id
+    let client_id = rand::random::<u64>();
+
+    let authentication = ClientAuthentication::Unsecure {
+        client_id,
+        server_addr,
+        protocol_id: PROTOCOL_ID,
+        user_data: Some(name_to_user_data(player_name)),
+    };
+
+    Ok(RenetClient::new(
+        current_time,
+        socket,
+        RenetConnectionConfig::default(),
+        authentication,
+    )?)
+}
+
+fn name_to_user_data(name: &str) -> [u8; NETCODE_USER_DATA_BYTES] {
+    let mut user_data = [0u8; NETCODE_USER_DATA_BYTES];
+    let mut len = name.len().min(NETCODE_USER_DATA_BYTES - 1);
+    while !name.is_char_boundary(len) {
+        len -= 1;
+    }
+
+    user_data[0] = len as u8;
+    user_data[1..=len].copy_from_slice(&name.as_bytes()[..len]);
+    user_data
+}
+
+fn name_from_user_data(user_data: &[u8; NETCODE_USER_DATA_BYTES]) -> String {
+    let len = user_data[0] as usize;
+    String::from_utf8_lossy(&user_data[1..=len]).to_string()
+}
+
+fn log_network_errors(mut errors: EventReader<RenetError>) {
+    for error in errors.iter() {
+        error!("network error: {}", error);
+    }
+}
+
+fn server_connections(
+    mut events: EventReader<ServerEvent>,
+    mut server: ResMut<RenetServer>,
+    mut lobby: ResMut<ServerLobby>,
+    levels: Res<Levels>,
+) {
+    for event in events.iter() {
+        match event {
+            ServerEvent::ClientConnected(id, user_data) => {
+                if lobby.started || lobby.players.len() >= lobby.max_players() {
+                    server.disconnect(*id);
+                    continue;
+                }
+
+                let name = name_from_user_data(user_data);
+                info!("{} joined the lobby", name);
+
+                let color = lobby.free_color();
+                lobby
+                    .players
+                    .insert(*id, ServerPlayer::new(name, color, &levels));
+                broadcast_lobby(&mut server, &lobby);
+
+                // a versus game starts as soon as the opponent is there, the
+                // co-op host chooses when to start
+                if lobby.mode == MatchMode::Versus && lobby.can_start() {
+                    start_match(&mut server, &mut lobby);
+                }
+            }
+            ServerEvent::ClientDisconnected(id) => {
+                let Some(player) = lobby.players.remove(id) else {
+                    continue;
+                };
+                info!("{} left the game", player.name);
+
+                if !lobby.started {
+                    broadcast_lobby(&mut server, &lobby);
+                    continue;
+                }
+
+                match lobby.mode {
+                    // the last player standing wins
+                    MatchMode::Versus if !lobby.finished => {
+                        if let Some(winner_id) = lobby.players.keys().next().copied() {
+                            end_versus(&mut server, &mut lobby, winner_id);
+                        }
+                    }
+                    MatchMode::Versus => {}
+                    // the words typed by the player are free again
+                    MatchMode::Coop => release_claims(&mut server, &mut lobby, *id),
+                }
+            }
+        }
+    }
+}
+
+fn broadcast_lobby(server: &mut RenetServer, lobby: &ServerLobby) {
+    server.broadcast_message(
+        RELIABLE_CHANNEL,
+        encode(&ServerMessage::Lobby {
+            mode: lobby.mode,
+            players: lobby.player_infos(),
+        }),
+    );
+}
+
+pub fn start_match(server: &mut RenetServer, lobby: &mut ServerLobby) {
+    lobby.started = true;
+    // the ids of the enemies start over with the match
+    lobby.claims.clear();
+
+    server.broadcast_message(
+        RELIABLE_CHANNEL,
+        encode(&ServerMessage::StartGame {
+            mode: lobby.mode,
+            players: lobby.player_infos(),
+        }),
+    );
+}
+
+fn end_versus(server: &mut RenetServer, lobby: &mut ServerLobby, winner_id: u64) {
+    lobby.finished = true;
+
+    let winner = lobby
+        .players
+        .get(&winner_id)
+        .map(|player| player.name.clone())
+        .unwrap_or_default();
+
+    server.broadcast_message(
+        RELIABLE_CHANNEL,
+        encode(&ServerMessage::GameOver { winner_id, winner }),
+    );
+}
+
+fn release_claims(server: &mut RenetServer, lobby: &mut ServerLobby, player_id: u64) {
+    for (enemy, claim) in lobby.claims.iter_mut() {
+        if claim.owner != Some(player_id) {
+            continue;
+        }
+
+        claim.owner = None;
+        server.broadcast_message(
+            RELIABLE_CHANNEL,
+            encode(&ServerMessage::Claimed {
+                enemy: *enemy,
+                owner: None,
+                remaining: claim.remaining.clone(),
+            }),
+        );
+    }
+}
+
+fn server_receive(
+    mut server: ResMut<RenetServer>,
+    mut lobby: ResMut<ServerLobby>,
+    levels: Res<Levels>,
+    archetypes: Res<EnemyArchetypes>,
+) {
+    for client_id in server.clients_id() {
+        while let Some(bytes) = server.receive_message(client_id, RELIABLE_CHANNEL) {
+            let Some(message) = decode::<ClientMessage>(&bytes) else {
+                warn!("invalid message from client {}", client_id);
+                continue;
+            };
+
+            if !lobby.started || lobby.finished {
+                continue;
+            }
+
+            match lobby.mode {
+                MatchMode::Versus => receive_versus(
+                    &mut server,
+                    &mut lobby,
+                    &levels,
+                    &archetypes,
+                    client_id,
+                    message,
+                ),
+                MatchMode::Coop => receive_coop(&mut server, &mut lobby, client_id, message),
+            }
+        }
+    }
+}
+
+fn receive_versus(
+    server: &mut RenetServer,
+    lobby: &mut ServerLobby,
+    levels: &Levels,
+    archetypes: &EnemyArchetypes,
+    client_id: u64,
+    message: ClientMessage,
+) {
+    let Some(player) = lobby.players.get_mut(&client_id) else {
+        return;
+    };
+
+    match message {
+        ClientMessage::EnemyBorn {
+            enemy,
+            archetype,
+            generated,
+        } => {
+            let kind = archetypes.find(&archetype);
+            let Some(kind) = kind.filter(|kind| player.accept_spawn(*kind, generated, archetypes))
+            else {
+                warn!("client {} spawned an unexpected {:?}", client_id, archetype);
+                return;
+            };
+
+            if !generated {
+                player.stage.enemy_born(archetypes[kind].is_long());
+            }
+            player.enemies.insert(enemy, FieldEnemy { kind, generated });
+        }
+        ClientMessage::EnemyDefeated { enemy } => {
+            let Some(enemy) = player.enemies.remove(&enemy) else {
+                warn!("client {} defeated the unknown enemy {}", client_id, enemy);
+                return;
+            };
+            let archetype = &archetypes[enemy.kind];
+
+            // the misses stay on the client, the points are the best the
+            // player could make
+            player.score += rules::defeat_points(archetype.points, player.stage.bonus());
+            player.stage.increase_bonus();
+            if !enemy.generated {
+                player.stage.enemy_defeated();
+            }
+            level_up_when_cleared(server, player, levels, client_id);
+
+            // killing a big spider sends little ones to the opponent
+            if archetype.is_long() {
+                for (_, opponent) in lobby.players.iter_mut().filter(|(id, _)| **id != client_id) {
+                    opponent.spiders_sent += SPIDERS_PER_LONG_WORD;
+                }
+                server.broadcast_message_except(
+                    client_id,
+                    RELIABLE_CHANNEL,
+                    encode(&ServerMessage::SendSpiders {
+                        count: SPIDERS_PER_LONG_WORD,
+                    }),
+                );
+            }
+        }
+        ClientMessage::EnemyReachedIgloo { enemy } => {
+            let Some(enemy) = player.enemies.remove(&enemy) else {
+                warn!("client {} lost the unknown enemy {}", client_id, enemy);
+                return;
+            };
+
+            let damage = archetypes[enemy.kind].damage;
+            player.stage.igloo_hit(damage, enemy.generated);
+            level_up_when_cleared(server, player, levels, client_id);
+        }
+        ClientMessage::Defeated => {
+            let winner_id = lobby
+                .players
+                .keys()
+                .find(|id| **id != client_id)
+                .copied()
+                .unwrap_or_default();
+
+            end_versus(server, lobby, winner_id);
+        }
+        _ => warn!("unexpected {:?} in a versus game", message),
+    }
+}
+
+fn level_up_when_cleared(
+    server: &mut RenetServer,
+    player: &mut ServerPlayer,
+    levels: &Levels,
+    client_id: u64,
+) {
+    if player.stage.is_cleared() {
+        player.stage.level_up(levels);
+        let level = player.stage.level;
+        server.send_message(
+            client_id,
+            RELIABLE_CHANNEL,
+            encode(&ServerMessage::LevelUp { level }),
+        );
+    }
+}
+
+/// The first player to claim an enemy types it alone, the others are told who
+/// holds it and what is left of its word.
+fn receive_coop(
+    server: &mut RenetServer,
+    lobby: &mut ServerLobby,
+    client_id: u64,
+    message: ClientMessage,
+) {
+    match message {
+        ClientMessage::Claim { enemy } => {
+            let Some(claim) = lobby.claims.get_mut(&enemy) else {
+                return;
+            };
+
+            let owner = *claim.owner.get_or_insert(client_id);
+            let message = encode(&ServerMessage::Claimed {
+                enemy,
+                owner: Some(owner),
+                remaining: claim.remaining.clone(),
+            });
+
+            if owner == client_id {
+                server.broadcast_message(RELIABLE_CHANNEL, message);
+            } else {
+                server.send_message(client_id, RELIABLE_CHANNEL, message);
+            }
+        }
+        ClientMessage::Typed { enemy, char } => {
+            let Some(claim) = lobby.claims.get_mut(&enemy) else {
+                return;
+            };
+
+            // the player lost the race for this enemy, correct its field
+            if claim.owner != Some(client_id) {
+                let message = encode(&ServerMessage::Claimed {
+                    enemy,
+                    owner: claim.owner,
+                    remaining: claim.remaining.clone(),
+                });
+                server.send_message(client_id, RELIABLE_CHANNEL, message);
+                return;
+            }
+
+            if !claim.remaining.starts_with(char) {
+                warn!("client {} typed {:?} out of sync", client_id, char);
+                return;
+            }
+
+            claim.remaining.remove(0);
+            if claim.remaining.is_empty() {
+                lobby.claims.remove(&enemy);
+            }
+
+            server.broadcast_message(
+                RELIABLE_CHANNEL,
+                encode(&ServerMessage::Typed {
+                    enemy,
+                    player: client_id,
+                }),
+            );
+        }
+        ClientMessage::Score { score, combos } => {
+            if let Some(player) = lobby.players.get_mut(&client_id) {
+                player.score = score;
+                player.combos = combos;
+            }
+
+            let scores = lobby
+                .players
+                .iter()
+                .map(|(id, player)| PlayerScoreInfo {
+                    id: *id,
+                    score: player.score,
+                    combos: player.combos,
+                })
+                .collect();
+            server.broadcast_message(RELIABLE_CHANNEL, encode(&ServerMessage::Scoreboard(scores)));
+        }
+        _ => warn!("unexpected {:?} in a co-op game", message),
+    }
+}
@@ -0,0 +1,184 @@
+use bevy::prelude::*;
+
+use crate::{
+    assets::FontsAtlas,
+    main_menu::spawn_button,
+    settings::{spawn_settings_menu, Settings, SettingsButton},
+    words::WordsResource,
+    GameMode, GameState,
+};
+
+#[derive(Component)]
+pub struct PauseUIRoot;
+
+#[derive(Component)]
+pub struct ResumeButton;
+
+#[derive(Component)]
+pub struct RestartButton;
+
+#[derive(Component)]
+pub struct QuitButton;
+
+pub struct PausePlugin;
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_update(GameState::Gameplay).with_system(pause_game))
+            .add_system_set(SystemSet::on_enter(GameState::Paused).with_system(spawn_pause_menu))
+            .add_system_set(
+                SystemSet::on_update(GameState::Paused)
+                    .with_system(resume_game)
+                    .with_system(resume_button_clicked)
+                    .with_system(restart_button_clicked)
+                    .with_system(settings_button_clicked)
+                    .with_system(quit_button_clicked),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::Paused).with_system(despawn_pause_menu));
+    }
+}
+
+/// Only solo games are paused, the other players of a multiplayer game go on.
+pub fn pause_game(
+    mut keys: ResMut<Input<KeyCode>>,
+    mode: Res<GameMode>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    if *mode == GameMode::Solo && keys.clear_just_pressed(KeyCode::Escape) {
+        // `Err` when the game over is already queued, it wins over the pause
+        let _ = game_state.push(GameState::Paused);
+    }
+}
+
+fn resume_game(mut keys: ResMut<Input<KeyCode>>, mut game_state: ResMut<State<GameState>>) {
+    if keys.clear_just_pressed(KeyCode::Escape) {
+        // `Err` when the Resume button was clicked in the same frame
+        let _ = game_state.pop();
+    }
+}
+
+fn spawn_pause_menu(mut commands: Commands, font_server: Res<FontsAtlas>) {
+    let resume_button = spawn_button(&mut commands, &font_server, "Resume", Color::RED);
+    commands.entity(resume_button).insert(ResumeButton);
+
+    let restart_button = spawn_button(&mut commands, &font_server, "Restart", Color::RED);
+    commands.entity(restart_button).insert(RestartButton);
+
+    let settings_button = spawn_button(
+        &mut commands,
+        &font_server,
+        "Settings",
+        Color::rgb(0.4, 0.4, 0.9),
+    );
+    commands.entity(settings_button).insert(SettingsButton);
+
+    let quit_button = spawn_button(
+        &mut commands,
+        &font_server,
+        "Quit",
+        Color::rgb(0.4, 0.4, 0.9),
+    );
+    commands.entity(quit_button).insert(QuitButton);
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            // the frozen game stays visible behind the menu
+            background_color: BackgroundColor(Color::rgba(1.0, 1.0, 1.0, 0.8)),
+            ..default()
+        })
+        .insert(PauseUIRoot)
+        .with_children(|commands| {
+            commands.spawn(TextBundle {
+                style: Style {
+                    align_self: AlignSelf::Center,
+                    margin: UiRect::all(Val::Percent(3.0)),
+                    ..default()
+                },
+                text: Text::from_section(
+                    "Paused",
+                    TextStyle {
+                        font: font_server.common_font.clone(),
+                        font_size: 56.0,
+                        color: Color::BLACK,
+                    },
+                ),
+                ..default()
+            });
+        })
+        .add_child(resume_button)
+        .add_child(restart_button)
+        .add_child(settings_button)
+        .add_child(quit_button);
+}
+
+fn despawn_pause_menu(mut commands: Commands, pause_root: Query<Entity, With<PauseUIRoot>>) {
+    for entity in pause_root.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn resume_button_clicked(
+    interactions: Query<&Interaction, (With<ResumeButton>, Changed<Interaction>)>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Clicked) {
+            // `Err` when Escape was pressed in the same frame
+            let _ = game_state.pop();
+            return;
+        }
+    }
+}
+
+/// The paused game is left for a new one, which starts from the first level.
+fn restart_button_clicked(
+    interactions: Query<&Interaction, (With<RestartButton>, Changed<Interaction>)>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Clicked) {
+            // `Err` when Escape resumed the game in the same frame
+            let _ = game_state.replace(GameState::Gameplay);
+            return;
+        }
+    }
+}
+
+fn quit_button_clicked(
+    interactions: Query<&Interaction, (With<QuitButton>, Changed<Interaction>)>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Clicked) {
+            // `Err` when Escape resumed the game in the same frame
+            let _ = game_state.replace(GameState::MainMenu);
+            return;
+        }
+    }
+}
+
+fn settings_button_clicked(
+    mut commands: Commands,
+    interactions: Query<&Interaction, (With<SettingsButton>, Changed<Interaction>)>,
+    pause_root: Query<Entity, With<PauseUIRoot>>,
+    font_server: Res<FontsAtlas>,
+    settings: Res<Settings>,
+    words: Res<WordsResource>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Clicked) {
+            let settings_root = spawn_settings_menu(&mut commands, &font_server, &settings, &words);
+
+            // despawned along with the pause menu
+            commands
+                .entity(pause_root.single())
+                .add_child(settings_root);
+        }
+    }
+}
@@ -1,5 +1,9 @@
+use std::collections::BTreeMap;
+
 use bevy::prelude::*;
 
+use crate::rules::Combo;
+
 #[derive(Resource, Debug)]
 pub struct PlayerScore(pub u32);
 
@@ -12,8 +16,67 @@ impl PlayerScore {
 #[derive(Resource, Debug, Default)]
 pub struct PlayerStats {
     pub misses: u32,
-    pub combos_count: u32,
+    pub combo: Combo,
+
+    pub hits: u32,           // keystrokes matching the next character of the target
+    pub finished_chars: u32, // hits in words typed until the end
+    pub play_time: f32,      // in seconds, without the pauses
+    pub reaction_time: f32,  // sum of the delays from spawn to first keystroke, in seconds
+    pub reactions: u32,
+    pub key_misses: BTreeMap<char, u32>, // by expected key, or typed key without target
+}
+
+impl PlayerStats {
+    pub fn record_miss(&mut self, key: char) {
+        self.misses += 1;
+        *self.key_misses.entry(key.to_ascii_lowercase()).or_default() += 1;
+    }
+
+    pub fn keystrokes(&self) -> u32 {
+        self.hits + self.misses
+    }
+
+    /// Words per minute, a word being 5 correct keystrokes.
+    pub fn wpm(&self) -> f32 {
+        if self.play_time <= 0.0 {
+            return 0.0;
+        }
+
+        self.hits as f32 / 5.0 / (self.play_time / 60.0)
+    }
+
+    /// Share of the keystrokes that were correct.
+    pub fn raw_accuracy(&self) -> f32 {
+        ratio(self.hits, self.keystrokes())
+    }
+
+    /// Share of the keystrokes that ended up in a finished word.
+    pub fn net_accuracy(&self) -> f32 {
+        ratio(self.finished_chars, self.keystrokes())
+    }
+
+    pub fn mean_reaction_time(&self) -> Option<f32> {
+        (self.reactions > 0).then(|| self.reaction_time / self.reactions as f32)
+    }
+}
+
+fn ratio(count: u32, total: u32) -> f32 {
+    if total == 0 {
+        return 0.0;
+    }
+
+    count as f32 / total as f32
 }
 
 #[derive(Component)]
 pub struct PlayerCombosChanged;
+
+/// Color of the words targeted by the player.
+#[derive(Resource, Debug)]
+pub struct PlayerColor(pub Color);
+
+impl Default for PlayerColor {
+    fn default() -> Self {
+        Self(Color::RED)
+    }
+}
@@ -0,0 +1,219 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    archetypes::PowerUp,
+    assets::{EntitiesAtlas, FontsAtlas},
+    boss::Segments,
+    enemy::{
+        throw_fish, ClaimedBy, DefeatEnemy, Enemy, EnemyAnimated, EnemyGenitor, EnemyKilled,
+        EnemyState, Target,
+    },
+    words::TextEnemy,
+    GameMode, GameState, CASE_SIZE, WINDOW_HEIGHT,
+};
+
+pub const FREEZE_DURATION: f32 = 3.0; // in seconds
+pub const SLOW_MOTION_DURATION: f32 = 6.0; // in seconds
+pub const SLOW_MOTION_SPEED: f32 = 0.5; // factor of the enemies speed
+
+/// Power-ups granted by the enemies typed by the player, while they last.
+/// They are not shared in co-op, where the power-up enemies are plain ones.
+#[derive(Resource, Default)]
+pub struct PowerUps {
+    freeze: Option<Timer>,
+    slow_motion: Option<Timer>,
+}
+
+#[derive(Component)]
+pub struct PowerUpsText;
+
+impl PowerUps {
+    /// Factor of the enemies speed, 0.0 while they are frozen.
+    pub fn speed_factor(&self) -> f32 {
+        if self.freeze.is_some() {
+            0.0
+        } else if self.slow_motion.is_some() {
+            SLOW_MOTION_SPEED
+        } else {
+            1.0
+        }
+    }
+
+    fn activate(&mut self, power_up: PowerUp) {
+        let timer = |seconds| Some(Timer::from_seconds(seconds, TimerMode::Once));
+
+        match power_up {
+            PowerUp::Freeze => self.freeze = timer(FREEZE_DURATION),
+            PowerUp::SlowMotion => self.slow_motion = timer(SLOW_MOTION_DURATION),
+            PowerUp::Bomb => {}
+        }
+    }
+
+    fn tick(&mut self, delta: Duration) {
+        for timer in [&mut self.freeze, &mut self.slow_motion] {
+            if timer
+                .as_mut()
+                .is_some_and(|timer| timer.tick(delta).finished())
+            {
+                *timer = None;
+            }
+        }
+    }
+
+    /// Running power-ups and their remaining seconds.
+    fn remaining(&self) -> impl Iterator<Item = (&'static str, f32)> + '_ {
+        [("Freeze", &self.freeze), ("Slow motion", &self.slow_motion)]
+            .into_iter()
+            .filter_map(|(name, timer)| Some((name, timer.as_ref()?.remaining_secs())))
+    }
+}
+
+pub struct PowerUpsPlugin;
+impl Plugin for PowerUpsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PowerUps>()
+            .add_startup_system(draw_power_ups)
+            // bombs defeat enemies like the typed ones, before the points are given
+            .add_system_to_stage(CoreStage::PreUpdate, use_power_ups.after("key_pressed"))
+            .add_system_set(SystemSet::on_enter(GameState::Gameplay).with_system(reset_power_ups))
+            .add_system_set(
+                SystemSet::on_update(GameState::Gameplay)
+                    .with_system(tick_power_ups)
+                    .with_system(power_ups_update),
+            );
+    }
+}
+
+fn reset_power_ups(mut power_ups: ResMut<PowerUps>) {
+    *power_ups = PowerUps::default();
+}
+
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+fn use_power_ups(
+    mut commands: Commands,
+    mut killed: EventReader<EnemyKilled>,
+    mut defeat: EventWriter<DefeatEnemy>,
+    mut power_ups: ResMut<PowerUps>,
+    mut enemies: Query<
+        (
+            Entity,
+            &mut Enemy,
+            &Children,
+            Option<&mut Segments>,
+            Option<&Target>,
+        ),
+        Without<ClaimedBy>,
+    >,
+    mut texts: Query<(&mut Text, &GlobalTransform), With<TextEnemy>>,
+    atlases: Res<EntitiesAtlas>,
+    mode: Res<GameMode>,
+    game_state: Res<State<GameState>>,
+) {
+    if *game_state.current() != GameState::Gameplay || !mode.has_own_field() {
+        killed.clear();
+        return;
+    }
+
+    for power_up in killed.iter().filter_map(|killed| killed.power_up) {
+        power_ups.activate(power_up);
+
+        if power_up != PowerUp::Bomb {
+            continue;
+        }
+
+        for (entity, mut enemy, children, segments, target) in enemies.iter_mut() {
+            if enemy.state == EnemyState::Idle {
+                continue;
+            }
+            let Ok((mut text, transform)) = texts.get_mut(children[0]) else {
+                continue;
+            };
+
+            // a fish for every character left, as if the word was typed
+            let mut left = text.sections[0].value.chars().count();
+            text.sections[0].value.clear();
+            if let Some(mut segments) = segments {
+                left += segments
+                    .next
+                    .drain(..)
+                    .map(|segment| segment.chars().count())
+                    .sum::<usize>();
+                text.sections[1].value.clear();
+            }
+
+            for _ in 0..left {
+                throw_fish(
+                    &mut commands,
+                    &atlases,
+                    entity,
+                    transform.translation().truncate(),
+                );
+            }
+
+            enemy.state = EnemyState::Idle;
+            commands.entity(entity).remove::<EnemyAnimated>();
+
+            // the target being typed is rewarded when it is lost, like a typed word
+            if target.is_some() {
+                commands.entity(entity).remove::<Target>();
+            } else {
+                defeat.send(DefeatEnemy { enemy: entity });
+            }
+        }
+    }
+}
+
+fn tick_power_ups(
+    time: Res<Time>,
+    mut power_ups: ResMut<PowerUps>,
+    mut genitors: Query<&mut EnemyGenitor>,
+) {
+    // the generations are delayed as much as the enemies are slowed down
+    let delay = time.delta_seconds_f64() * (1.0 - power_ups.speed_factor() as f64);
+    if delay > 0.0 {
+        for mut genitor in genitors.iter_mut() {
+            genitor.last_generation += delay;
+        }
+    }
+
+    power_ups.tick(time.delta());
+}
+
+fn draw_power_ups(mut commands: Commands, fonts: Res<FontsAtlas>) {
+    // on the left of the stage data
+    let x = -CASE_SIZE * 1.5;
+    let y = -WINDOW_HEIGHT / 2.0 + CASE_SIZE * 2.2;
+
+    commands
+        .spawn(Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: fonts.common_font.clone(),
+                    font_size: 22.0,
+                    color: Color::rgb(0.2, 0.4, 0.9),
+                },
+            )
+            .with_alignment(TextAlignment::BOTTOM_RIGHT),
+            transform: Transform::from_translation(Vec3::new(x, y, 5.0)),
+            ..default()
+        })
+        .insert(PowerUpsText);
+}
+
+fn power_ups_update(power_ups: Res<PowerUps>, mut text: Query<&mut Text, With<PowerUpsText>>) {
+    let Ok(mut text) = text.get_single_mut() else {
+        return;
+    };
+
+    let value = power_ups
+        .remaining()
+        .map(|(name, seconds)| format!("{name} {seconds:.1}s"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
@@ -0,0 +1,625 @@
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bevy::{
+    prelude::*,
+    time::TimeSystem,
+    utils::Instant,
+    window::{PresentMode, WindowId},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    assets::FontsAtlas,
+    cli::Args,
+    daily::DailyChallenge,
+    gameplay::RunDifficulty,
+    high_scores::config_dir,
+    player::{PlayerScore, PlayerStats},
+    rng::GameRng,
+    rules::Difficulty,
+    stage::StageComponent,
+    words::WordsResource,
+    GameMode, GameState, WINDOW_HEIGHT,
+};
+
+pub const REPLAYS_DIR: &str = "replays";
+pub const BEST_REPLAY_FILE: &str = "best_replay.ron"; // kept aside from the pruned replays
+pub const MAX_REPLAYS: usize = 50; // the oldest ones are removed
+pub const FAST_FORWARD_SPEED: f64 = 4.0;
+pub const MAX_PLAYBACK_LAG: f64 = 0.25; // in seconds, frames late by more are slowed down
+
+/// Inputs of a solo run, played again through the gameplay systems. The
+/// enemies come from the seed, the run only depends on what was typed and
+/// when: every gameplay frame is kept with its duration.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Replay {
+    pub version: String, // of the game, other versions may play it differently
+    #[serde(default)]
+    pub player: String,
+    pub seed: u64,
+    pub language: String, // code of the language pack
+    pub date: String,     // YYYY-MM-DD HH:MM:SS, local time
+    pub score: u32,
+    pub stage: u16,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+
+    pub frames: Vec<u64>,           // duration of each gameplay frame, in ns
+    pub inputs: Vec<(usize, char)>, // characters typed and the frame they were typed on
+    #[serde(default)]
+    pub progress: Vec<Progress>, // raced by the ghosts, see `ghost::Ghost`
+}
+
+/// Score of a run when it changed.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Progress {
+    pub time: f32, // of play, in seconds without the pauses
+    pub score: u32,
+    pub stage: u16,
+    pub combo: u32,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+    Serialize(ron::Error),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(error) => write!(f, "cannot access the replay: {error}"),
+            ReplayError::Parse(error) => write!(f, "invalid replay file: {error}"),
+            ReplayError::Serialize(error) => write!(f, "cannot save the replay: {error}"),
+        }
+    }
+}
+
+/// Run being played, recorded from its first gameplay frame.
+#[derive(Resource, Default)]
+pub struct ReplayRecorder {
+    replay: Option<Replay>,
+    typed: Vec<char>, // during the current frame, before `key_pressed` reads them
+}
+
+/// Last run played or watched, watched again from the game over screen.
+#[derive(Resource)]
+pub struct LastReplay(pub Replay);
+
+/// Replay watched instead of a played run. The time of the game is the one of
+/// the recorded frames, the keyboard only controls the playback.
+#[derive(Resource)]
+pub struct ReplayPlayer {
+    pub replay: Replay,
+    pub paused: bool,
+    pub fast_forward: bool,
+
+    frame: usize,        // next frame to play
+    input: usize,        // next input to type
+    clock: Option<Time>, // time of the replayed run, `None` out of the gameplay
+    last_real: Instant,
+    lag: f64, // real time not played yet, in seconds
+}
+
+#[derive(Component)]
+pub struct WatchReplayButton;
+
+#[derive(Component)]
+pub struct ReplayText;
+
+impl Replay {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        let content = fs::read_to_string(path).map_err(ReplayError::Io)?;
+        ron::from_str(&content).map_err(ReplayError::Parse)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(ReplayError::Io)?;
+        }
+
+        // thousands of frames, on a single line
+        let config = ron::ser::PrettyConfig::default().compact_arrays(true);
+        let content = ron::ser::to_string_pretty(self, config).map_err(ReplayError::Serialize)?;
+        fs::write(path, content).map_err(ReplayError::Io)
+    }
+
+    /// Save the replay in the user config directory, named after its date,
+    /// and remove the oldest ones. The best run is also saved aside, in
+    /// `BEST_REPLAY_FILE`. Returns the path of the saved file.
+    pub fn save_to_config_dir(&self) -> Result<Option<PathBuf>, ReplayError> {
+        let Some(dir) = config_dir() else {
+            return Ok(None);
+        };
+
+        let best = Self::best().map_or(0, |best| best.score);
+        if self.score > best {
+            self.save(dir.join(BEST_REPLAY_FILE))?;
+        }
+
+        let dir = dir.join(REPLAYS_DIR);
+        let path = dir.join(format!("{}.ron", self.date.replace([' ', ':'], "-")));
+        self.save(&path)?;
+
+        let replays = Self::saved_in(&dir).map_err(ReplayError::Io)?;
+        for old in replays.iter().rev().skip(MAX_REPLAYS) {
+            fs::remove_file(old).map_err(ReplayError::Io)?;
+        }
+
+        Ok(Some(path))
+    }
+
+    /// Replay of the best run saved in the user config directory.
+    pub fn best() -> Option<Self> {
+        Self::load(config_dir()?.join(BEST_REPLAY_FILE)).ok()
+    }
+
+    /// Most recent replay of the user config directory.
+    pub fn latest() -> Option<PathBuf> {
+        let dir = config_dir()?.join(REPLAYS_DIR);
+        Self::saved_in(&dir).ok()?.pop()
+    }
+
+    /// Replay files of `dir`, oldest first.
+    fn saved_in(dir: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut paths = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "ron"))
+            .collect::<Vec<_>>();
+        paths.sort();
+        Ok(paths)
+    }
+}
+
+impl ReplayRecorder {
+    fn start(&mut self) {
+        self.replay = Some(Replay {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            ..default()
+        });
+        self.typed.clear();
+    }
+
+    fn is_recording(&self) -> bool {
+        self.replay.is_some()
+    }
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            paused: false,
+            fast_forward: false,
+            frame: 0,
+            input: 0,
+            clock: None,
+            last_real: Instant::now(),
+            lag: 0.0,
+        }
+    }
+
+    /// Play the replay from its first frame, with `time` as its clock.
+    fn start(&mut self, time: &mut Time) {
+        self.frame = 0;
+        self.input = 0;
+        self.clock = Some(time.clone());
+        self.last_real = Instant::now();
+        self.lag = 0.0;
+
+        // the first frame is the one entering the gameplay, nothing is typed yet
+        let delta = self.next_frame().unwrap_or_default();
+        self.tick_clock(delta, time);
+        self.next_inputs();
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.frame >= self.replay.frames.len()
+    }
+
+    fn next_frame(&mut self) -> Option<Duration> {
+        let nanos = *self.replay.frames.get(self.frame)?;
+        self.frame += 1;
+        Some(Duration::from_nanos(nanos))
+    }
+
+    /// Characters typed on the frame just played.
+    fn next_inputs(&mut self) -> Vec<char> {
+        let mut typed = vec![];
+        while let Some((frame, char)) = self.replay.inputs.get(self.input) {
+            if *frame >= self.frame {
+                break;
+            }
+            typed.push(*char);
+            self.input += 1;
+        }
+        typed
+    }
+
+    /// Advance the clock of the replayed run and make it the time of the game.
+    fn tick_clock(&mut self, delta: Duration, time: &mut Time) {
+        let Some(clock) = &mut self.clock else {
+            return;
+        };
+
+        let last_update = clock.last_update().unwrap_or_else(|| clock.startup());
+        clock.update_with_instant(last_update + delta);
+        *time = clock.clone();
+    }
+
+    /// Whether the next frame is due, after `real` seconds of playback.
+    fn is_due(&mut self, real: f64) -> bool {
+        let speed = if self.fast_forward {
+            FAST_FORWARD_SPEED
+        } else {
+            1.0
+        };
+        self.lag = (self.lag + real * speed).min(MAX_PLAYBACK_LAG * speed);
+
+        let Some(next) = self.replay.frames.get(self.frame) else {
+            return false;
+        };
+        let next = Duration::from_nanos(*next).as_secs_f64();
+        if self.lag < next {
+            return false;
+        }
+
+        self.lag -= next;
+        true
+    }
+}
+
+pub struct ReplayPlugin;
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplayRecorder>()
+            .add_system_to_stage(CoreStage::First, play_replay.after(TimeSystem))
+            .add_system_to_stage(CoreStage::PreUpdate, record_typed.before("key_pressed"))
+            .add_system_set(
+                SystemSet::on_enter(GameState::Gameplay)
+                    .with_system(start_recording)
+                    .with_system(start_playback),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Gameplay)
+                    .with_system(record_frame)
+                    .with_system(control_playback)
+                    .with_system(replay_text_update),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Gameplay)
+                    .with_system(save_recording)
+                    .with_system(stop_playback),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::MainMenu).with_system(watch_saved_replay),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::GameOver).with_system(watch_last_replay),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::MainMenu).with_system(leave_replay));
+    }
+}
+
+/// Watch `replay` from its start, the run is played in solo with its seed.
+fn watch(commands: &mut Commands, replay: Replay, words: &mut WordsResource) {
+    if replay.version != env!("CARGO_PKG_VERSION") {
+        warn!(
+            "the replay was recorded with the version {}, it may play differently",
+            replay.version
+        );
+    }
+    if !words.select(&replay.language) {
+        warn!(
+            "no {} language pack, the replay words will differ",
+            replay.language
+        );
+    }
+
+    commands.remove_resource::<DailyChallenge>();
+    commands.insert_resource(LastReplay(replay.clone()));
+    commands.insert_resource(ReplayPlayer::new(replay));
+}
+
+/// The `--replay` file, or the most recent replay.
+fn watch_saved_replay(
+    mut commands: Commands,
+    interactions: Query<&Interaction, (With<WatchReplayButton>, Changed<Interaction>)>,
+    args: Res<Args>,
+    mut words: ResMut<WordsResource>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    if !interactions
+        .iter()
+        .any(|interaction| matches!(interaction, Interaction::Clicked))
+    {
+        return;
+    }
+
+    let Some(path) = args.replay.clone().or_else(Replay::latest) else {
+        warn!("no replay to watch yet");
+        return;
+    };
+
+    match Replay::load(&path) {
+        Ok(replay) => {
+            watch(&mut commands, replay, &mut words);
+            game_state.set(GameState::Gameplay).unwrap();
+        }
+        Err(error) => error!("{}: {}", path.display(), error),
+    }
+}
+
+fn watch_last_replay(
+    mut commands: Commands,
+    interactions: Query<&Interaction, (With<WatchReplayButton>, Changed<Interaction>)>,
+    last_replay: Option<Res<LastReplay>>,
+    mut words: ResMut<WordsResource>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    let Some(last_replay) = last_replay else {
+        return;
+    };
+
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Clicked) {
+            watch(&mut commands, last_replay.0.clone(), &mut words);
+            game_state.set(GameState::Gameplay).unwrap();
+            return;
+        }
+    }
+}
+
+fn leave_replay(mut commands: Commands) {
+    commands.remove_resource::<ReplayPlayer>();
+}
+
+/// Only solo runs are recorded, the others depend on the other players.
+fn start_recording(
+    mut recorder: ResMut<ReplayRecorder>,
+    mode: Res<GameMode>,
+    player: Option<Res<ReplayPlayer>>,
+) {
+    if *mode == GameMode::Solo && player.is_none() {
+        recorder.start();
+    } else {
+        recorder.replay = None;
+    }
+}
+
+/// Keep the characters `key_pressed` is about to read, the ones typed out of
+/// the gameplay are ignored like it does.
+fn record_typed(
+    mut inputs: EventReader<ReceivedCharacter>,
+    mut recorder: ResMut<ReplayRecorder>,
+    game_state: Res<State<GameState>>,
+) {
+    recorder.typed.clear();
+
+    if !recorder.is_recording() || *game_state.current() != GameState::Gameplay {
+        inputs.clear();
+        return;
+    }
+
+    // control characters are ignored by `key_pressed` too
+    recorder.typed.extend(
+        inputs
+            .iter()
+            .map(|input| input.char)
+            .filter(|char| !char.is_control()),
+    );
+}
+
+fn record_frame(
+    time: Res<Time>,
+    mut recorder: ResMut<ReplayRecorder>,
+    score: Res<PlayerScore>,
+    player_stats: Res<PlayerStats>,
+    stage: Query<&StageComponent>,
+) {
+    let ReplayRecorder { replay, typed } = &mut *recorder;
+    let Some(replay) = replay else {
+        return;
+    };
+
+    let frame = replay.frames.len();
+    replay.frames.push(time.delta().as_nanos() as u64);
+    replay
+        .inputs
+        .extend(typed.drain(..).map(|char| (frame, char)));
+
+    let progress = Progress {
+        time: player_stats.play_time,
+        score: score.0,
+        stage: stage.get_single().map(|stage| stage.level).unwrap_or(1),
+        combo: player_stats.combo.count,
+    };
+    let changed = |last: &Progress| {
+        (last.score, last.stage, last.combo) != (progress.score, progress.stage, progress.combo)
+    };
+    if replay.progress.last().is_none_or(changed) {
+        replay.progress.push(progress);
+    }
+}
+
+/// The run is over, or left from the pause menu.
+#[allow(clippy::too_many_arguments)]
+fn save_recording(
+    mut commands: Commands,
+    mut recorder: ResMut<ReplayRecorder>,
+    args: Res<Args>,
+    rng: Res<GameRng>,
+    words: Res<WordsResource>,
+    score: Res<PlayerScore>,
+    stage: Query<&StageComponent>,
+    difficulty: Res<RunDifficulty>,
+) {
+    let Some(mut replay) = recorder.replay.take() else {
+        return;
+    };
+
+    replay.player = args.player_name.clone();
+    replay.seed = rng.seed();
+    replay.language = words.current_pack().code.clone();
+    replay.date = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    replay.score = score.0;
+    replay.stage = stage.get_single().map(|stage| stage.level).unwrap_or(1);
+    replay.difficulty = difficulty.0;
+
+    match replay.save_to_config_dir() {
+        Ok(Some(path)) => info!("replay saved in {}", path.display()),
+        Ok(None) => warn!("no config directory, the replay won't be saved"),
+        Err(error) => error!("{}", error),
+    }
+
+    commands.insert_resource(LastReplay(replay));
+}
+
+fn start_playback(
+    mut commands: Commands,
+    player: Option<ResMut<ReplayPlayer>>,
+    mut time: ResMut<Time>,
+    fonts: Res<FontsAtlas>,
+) {
+    let Some(mut player) = player else {
+        return;
+    };
+
+    player.start(&mut time);
+
+    commands
+        .spawn(Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: fonts.common_font.clone(),
+                    font_size: 20.0,
+                    color: Color::rgb(0.4, 0.4, 0.9),
+                },
+            )
+            .with_alignment(TextAlignment::TOP_CENTER),
+            transform: Transform::from_translation(Vec3::new(0.0, WINDOW_HEIGHT / 2.0 - 10.0, 5.0)),
+            ..default()
+        })
+        .insert(ReplayText);
+}
+
+fn stop_playback(
+    mut commands: Commands,
+    player: Option<ResMut<ReplayPlayer>>,
+    replay_text: Query<Entity, With<ReplayText>>,
+    mut windows: ResMut<Windows>,
+) {
+    for entity in replay_text.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let Some(mut player) = player else {
+        return;
+    };
+
+    player.clock = None;
+    if let Some(window) = windows.get_mut(WindowId::primary()) {
+        window.set_present_mode(PresentMode::Fifo);
+    }
+}
+
+/// Play the recorded frames at their pace, the game stands still between
+/// them. Nothing is played while paused, the recorded pauses are skipped.
+fn play_replay(
+    player: Option<ResMut<ReplayPlayer>>,
+    mut time: ResMut<Time>,
+    mut inputs: ResMut<Events<ReceivedCharacter>>,
+    game_state: Res<State<GameState>>,
+) {
+    let Some(mut player) = player else {
+        return;
+    };
+    if player.clock.is_none() {
+        return;
+    }
+
+    // the keyboard doesn't type in a replay
+    inputs.clear();
+
+    let now = Instant::now();
+    let real = (now - player.last_real).as_secs_f64();
+    player.last_real = now;
+
+    let playing = *game_state.current() == GameState::Gameplay && !player.paused;
+    if !playing || !player.is_due(real) {
+        player.tick_clock(Duration::ZERO, &mut time);
+        return;
+    }
+
+    let delta = player.next_frame().unwrap_or_default();
+    player.tick_clock(delta, &mut time);
+    inputs.extend(
+        player
+            .next_inputs()
+            .into_iter()
+            .map(|char| ReceivedCharacter {
+                id: WindowId::primary(),
+                char,
+            }),
+    );
+}
+
+/// Space pauses the replay, F fast-forwards it as fast as the frames are drawn.
+fn control_playback(
+    keys: Res<Input<KeyCode>>,
+    player: Option<ResMut<ReplayPlayer>>,
+    mut windows: ResMut<Windows>,
+) {
+    let Some(mut player) = player else {
+        return;
+    };
+
+    if keys.just_pressed(KeyCode::Space) {
+        player.paused = !player.paused;
+    }
+    if keys.just_pressed(KeyCode::F) {
+        player.fast_forward = !player.fast_forward;
+
+        let present_mode = if player.fast_forward {
+            PresentMode::AutoNoVsync
+        } else {
+            PresentMode::Fifo
+        };
+        if let Some(window) = windows.get_mut(WindowId::primary()) {
+            window.set_present_mode(present_mode);
+        }
+    }
+}
+
+fn replay_text_update(
+    player: Option<Res<ReplayPlayer>>,
+    mut text: Query<&mut Text, With<ReplayText>>,
+) {
+    let (Some(player), Ok(mut text)) = (player, text.get_single_mut()) else {
+        return;
+    };
+
+    let status = if player.is_over() {
+        "End of replay".to_string()
+    } else if player.paused {
+        "Replay paused".to_string()
+    } else if player.fast_forward {
+        format!("Replay x{FAST_FORWARD_SPEED}")
+    } else {
+        "Replay".to_string()
+    };
+    let value = format!(
+        "{status} - seed {}\nSpace: pause, F: fast-forward",
+        player.replay.seed
+    );
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
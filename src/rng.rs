@@ -0,0 +1,160 @@
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    assets::FontsAtlas, daily::DailyChallenge, main_menu::MainMenuUIRoot, replay::ReplayPlayer,
+    GameState,
+};
+
+pub const MAX_SEED_DIGITS: usize = 19; // any of them fits in a u64
+pub const OTHERS_SEED_MIX: u64 = 0x9e37_79b9_7f4a_7c15; // odd, flips bits all over the seed
+
+/// Every random choice of a run: enemies, positions and words. A run is
+/// played again by starting it with the same seed.
+#[derive(Resource)]
+pub struct GameRng {
+    pub fixed_seed: Option<u64>, // seed of every run, a new random one when `None`
+    seed: u64,
+
+    /// Enemies planned by the levels, the same whatever the player does.
+    pub spawns: StdRng,
+    /// Enemies spawned by other enemies or by the other players.
+    pub others: StdRng,
+}
+
+#[derive(Component)]
+pub struct SeedText;
+
+impl GameRng {
+    pub fn new(fixed_seed: Option<u64>) -> Self {
+        let mut rng = Self {
+            fixed_seed,
+            seed: 0,
+            spawns: StdRng::seed_from_u64(0),
+            others: StdRng::seed_from_u64(0),
+        };
+        rng.start_run();
+        rng
+    }
+
+    /// Seed the next run with the fixed seed, or a new random one.
+    pub fn start_run(&mut self) {
+        let seed = self.fixed_seed.unwrap_or_else(|| rand::thread_rng().gen());
+        self.start_run_with(seed);
+    }
+
+    pub fn start_run_with(&mut self, seed: u64) {
+        self.seed = seed;
+        self.spawns = StdRng::seed_from_u64(seed);
+        // not `seed + 1`, the daily seeds follow each other and tomorrow's
+        // enemies would be today's generated ones
+        self.others = StdRng::seed_from_u64(seed ^ OTHERS_SEED_MIX);
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+pub struct RngPlugin;
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Gameplay).with_system(start_run))
+            .add_system_set(
+                SystemSet::on_update(GameState::MainMenu)
+                    .with_system(spawn_seed_text)
+                    .with_system(enter_seed),
+            );
+    }
+}
+
+/// The daily challenge has the seed of the day, a replay the one of its run.
+pub fn start_run(
+    mut rng: ResMut<GameRng>,
+    daily: Option<Res<DailyChallenge>>,
+    replay: Option<Res<ReplayPlayer>>,
+) {
+    match (replay, daily) {
+        (Some(replay), _) => rng.start_run_with(replay.replay.seed),
+        (None, Some(daily)) => rng.start_run_with(daily.seed),
+        (None, None) => rng.start_run(),
+    }
+}
+
+fn seed_label(rng: &GameRng) -> String {
+    match rng.fixed_seed {
+        Some(seed) => format!("Seed: {seed}_"),
+        None => "Seed: random, type digits to fix it".to_string(),
+    }
+}
+
+fn spawn_seed_text(
+    mut commands: Commands,
+    root: Query<Entity, With<MainMenuUIRoot>>,
+    seed_text: Query<Entity, With<SeedText>>,
+    font_server: Res<FontsAtlas>,
+    rng: Res<GameRng>,
+) {
+    let Ok(root) = root.get_single() else {
+        return;
+    };
+    if !seed_text.is_empty() {
+        return;
+    }
+
+    let text = commands
+        .spawn(TextBundle {
+            style: Style {
+                align_self: AlignSelf::Center,
+                ..default()
+            },
+            text: Text::from_section(
+                seed_label(&rng),
+                TextStyle {
+                    font: font_server.common_font.clone(),
+                    font_size: 22.0,
+                    color: Color::BLACK,
+                },
+            ),
+            ..default()
+        })
+        .insert(SeedText)
+        .id();
+
+    commands.entity(root).add_child(text);
+}
+
+/// Digits typed in the main menu fix the seed, backspace makes it random again.
+fn enter_seed(
+    mut inputs: EventReader<ReceivedCharacter>,
+    keys: Res<Input<KeyCode>>,
+    mut rng: ResMut<GameRng>,
+    mut seed_text: Query<&mut Text, With<SeedText>>,
+) {
+    let mut digits = rng
+        .fixed_seed
+        .map(|seed| seed.to_string())
+        .unwrap_or_default();
+
+    for input in inputs.iter() {
+        if input.char.is_ascii_digit() && digits.len() < MAX_SEED_DIGITS {
+            digits.push(input.char);
+        }
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        digits.pop();
+    }
+
+    let fixed_seed = digits.parse().ok();
+    if rng.fixed_seed != fixed_seed {
+        rng.fixed_seed = fixed_seed;
+    }
+
+    let Ok(mut text) = seed_text.get_single_mut() else {
+        return;
+    };
+    let label = seed_label(&rng);
+    if text.sections[0].value != label {
+        text.sections[0].value = label;
+    }
+}
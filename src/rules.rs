@@ -0,0 +1,227 @@
+//! Rules of the game, free of any Bevy type: the systems, the server, the bot
+//! simulation and the tools all play by them.
+
+use serde::{Deserialize, Serialize};
+
+pub const IGLOO_HEALTH: u32 = 10;
+pub const STAGE_CLEAR_HEAL: u32 = 2; // igloo health restored by a stage clear
+pub const BONUS_STEP: f32 = 0.2; // bonus ratio won by a defeated enemy, lost by a miss
+pub const MISS_PENALTY: f32 = 0.9; // share of its points an enemy keeps after a miss
+pub const LEVELS_PER_LONG_ENEMY: u16 = 3; // a long enemy more alive every 3 levels
+
+/// Preset of the solo runs, the enemies walk and spawn faster or slower.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+/// Enemies defeated in a row without a miss.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Combo {
+    pub count: u32,
+    pub max: u32, // longest combo of the run
+}
+
+impl Combo {
+    pub fn enemy_defeated(&mut self) {
+        self.count += 1;
+        self.max = self.max.max(self.count);
+    }
+
+    pub fn miss(&mut self) {
+        self.count = 0;
+    }
+}
+
+impl Difficulty {
+    pub fn next(self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy,
+        }
+    }
+
+    /// Factor of the enemy speeds.
+    pub fn speed_factor(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.75,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.3,
+        }
+    }
+
+    /// Factor of the pace of the stage spawns, the delays are divided by it.
+    pub fn spawn_rate(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.8,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.25,
+        }
+    }
+}
+
+/// Bonus ratio after an enemy is defeated.
+pub fn increased_bonus(bonus: f32) -> f32 {
+    bonus + BONUS_STEP
+}
+
+/// Bonus ratio after a miss, it never goes below zero.
+pub fn decreased_bonus(bonus: f32) -> f32 {
+    // the steps don't add up exactly in f32, the last one could go below
+    (bonus - BONUS_STEP).max(0.0)
+}
+
+/// Points given by a defeated enemy.
+pub fn defeat_points(points: u32, bonus: f32) -> u32 {
+    (points as f32 * (1.0 + bonus)) as u32
+}
+
+/// Points left to the target after a wrong key.
+pub fn missed_points(points: u32) -> u32 {
+    (points as f32 * MISS_PENALTY) as u32
+}
+
+/// The stage spawns enemies until the alive and defeated ones make its count.
+pub fn can_spawn_enemy(enemies_alive: usize, enemies_defeated: usize, to_defeat: usize) -> bool {
+    enemies_alive + enemies_defeated < to_defeat
+}
+
+/// Long enemies spawned by a stage of `level`, alive and defeated.
+pub fn max_long_enemies(level: u16) -> usize {
+    (level / LEVELS_PER_LONG_ENEMY) as usize
+}
+
+pub fn is_stage_cleared(enemies_alive: usize, enemies_defeated: usize, to_defeat: usize) -> bool {
+    enemies_defeated >= to_defeat && enemies_alive == 0
+}
+
+/// Igloo health once a stage is cleared.
+pub fn healed_igloo(health: u32) -> u32 {
+    (health + STAGE_CLEAR_HEAL).min(IGLOO_HEALTH)
+}
+
+/// Igloo health once an enemy reached it.
+pub fn damaged_igloo(health: u32, damage: u32) -> u32 {
+    health.saturating_sub(damage)
+}
+
+/// Speed of an enemy at `level`.
+pub fn speed_at_level(base: f32, per_level: f32, level: u16) -> f32 {
+    base + per_level * level as f32
+}
+
+/// Enemies of an endless level, at least one.
+pub fn endless_count(base: f32, per_level: f32, level: u16) -> usize {
+    let count = base + per_level * level as f32;
+    (count.round() as usize).max(1)
+}
+
+/// Delay between two spawns of an endless level, shrinking level after level
+/// down to `min_delay`.
+pub fn endless_spawn_delay(delay: f32, factor: f32, min_delay: f32, level: u16) -> f32 {
+    let delay = delay * factor.powi(level as i32 - 1);
+    delay.max(min_delay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combo_counts_defeats_until_a_miss() {
+        let mut combo = Combo::default();
+        combo.enemy_defeated();
+        combo.enemy_defeated();
+        combo.miss();
+        combo.enemy_defeated();
+
+        assert_eq!(combo, Combo { count: 1, max: 2 });
+    }
+
+    #[test]
+    fn harder_difficulties_are_faster() {
+        let difficulties = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+        for pair in difficulties.windows(2) {
+            assert!(pair[0].speed_factor() < pair[1].speed_factor());
+            assert!(pair[0].spawn_rate() < pair[1].spawn_rate());
+            assert_eq!(pair[0].next(), pair[1]);
+        }
+        assert_eq!(Difficulty::Hard.next(), Difficulty::Easy);
+    }
+
+    #[test]
+    fn bonus_never_goes_below_zero() {
+        assert_eq!(decreased_bonus(0.0), 0.0);
+        assert_eq!(decreased_bonus(increased_bonus(0.0)), 0.0);
+        assert!((increased_bonus(increased_bonus(0.0)) - 0.4).abs() < 1e-6);
+
+        let mut bonus = 0.0;
+        for _ in 0..3 {
+            bonus = increased_bonus(bonus);
+        }
+        for _ in 0..4 {
+            bonus = decreased_bonus(bonus);
+        }
+        assert_eq!(bonus, 0.0);
+    }
+
+    #[test]
+    fn bonus_multiplies_the_points() {
+        assert_eq!(defeat_points(10, 0.0), 10);
+        assert_eq!(defeat_points(10, 0.5), 15);
+        assert_eq!(defeat_points(5, 0.2), 6);
+    }
+
+    #[test]
+    fn miss_costs_a_tenth_of_the_points() {
+        assert_eq!(missed_points(10), 9);
+        assert_eq!(missed_points(5), 4);
+        assert_eq!(missed_points(0), 0);
+    }
+
+    #[test]
+    fn stage_spawns_its_count_of_enemies() {
+        assert!(can_spawn_enemy(2, 3, 6));
+        assert!(!can_spawn_enemy(3, 3, 6));
+        assert!(!can_spawn_enemy(0, 6, 6));
+    }
+
+    #[test]
+    fn long_enemies_come_every_three_levels() {
+        assert_eq!(max_long_enemies(1), 0);
+        assert_eq!(max_long_enemies(2), 0);
+        assert_eq!(max_long_enemies(3), 1);
+        assert_eq!(max_long_enemies(7), 2);
+    }
+
+    #[test]
+    fn stage_is_cleared_once_every_enemy_is_defeated() {
+        assert!(!is_stage_cleared(1, 5, 6));
+        assert!(!is_stage_cleared(1, 6, 6));
+        assert!(is_stage_cleared(0, 6, 6));
+    }
+
+    #[test]
+    fn igloo_health_stays_in_range() {
+        assert_eq!(healed_igloo(5), 5 + STAGE_CLEAR_HEAL);
+        assert_eq!(healed_igloo(IGLOO_HEALTH - 1), IGLOO_HEALTH);
+        assert_eq!(damaged_igloo(3, 2), 1);
+        assert_eq!(damaged_igloo(1, 2), 0);
+    }
+
+    #[test]
+    fn endless_levels_get_harder() {
+        assert_eq!(speed_at_level(1.0, 0.5, 2), 2.0);
+
+        assert_eq!(endless_count(0.2, 0.1, 1), 1);
+        assert_eq!(endless_count(4.0, 1.5, 10), 19);
+
+        assert_eq!(endless_spawn_delay(2.0, 0.5, 0.3, 1), 2.0);
+        assert_eq!(endless_spawn_delay(2.0, 0.5, 0.3, 2), 1.0);
+        assert_eq!(endless_spawn_delay(2.0, 0.5, 0.3, 10), 0.3);
+    }
+}
@@ -0,0 +1,347 @@
+use std::{fmt, fs, io, path::PathBuf};
+
+use bevy::{
+    prelude::*,
+    window::{WindowId, WindowMode},
+};
+use bevy_inspector_egui::WorldInspectorParams;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    assets::FontsAtlas,
+    daily::DailyChallenge,
+    gameplay::RunDifficulty,
+    high_scores::config_dir,
+    main_menu::spawn_button,
+    mixer::{MusicVolume, SfxVolume},
+    replay::ReplayPlayer,
+    rules::Difficulty,
+    words::{WordsResource, DEFAULT_LANGUAGE},
+    GameMode, GameState, WINDOW_HEIGHT, WINDOW_WIDTH,
+};
+
+pub const SETTINGS_FILE: &str = "settings.toml";
+pub const VOLUME_STEPS: f32 = 4.0; // the volumes go by quarters
+pub const WINDOW_SCALES: [f64; 5] = [0.75, 1.0, 1.25, 1.5, 2.0];
+
+/// Preferences of the player, saved in the user config directory. They are
+/// loaded before the window is created, see `main`.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub music_volume: f32, // from 0.0 to 1.0
+    pub sfx_volume: f32,
+    pub window_scale: Option<f64>, // of the window size, the screen one when `None`
+    pub fullscreen: bool,
+    pub language: String, // code of the language pack
+    pub difficulty: Difficulty,
+    pub inspector: bool, // world inspector overlay, for debugging
+}
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Serialize(toml::ser::Error),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::Io(error) => write!(f, "cannot access the settings: {error}"),
+            SettingsError::Parse(error) => write!(f, "invalid settings file: {error}"),
+            SettingsError::Serialize(error) => write!(f, "cannot save the settings: {error}"),
+        }
+    }
+}
+
+/// Settings shown over the main menu or the pause menu.
+#[derive(Component)]
+pub struct SettingsUIRoot;
+
+/// Opens the settings, in the main menu and the pause menu.
+#[derive(Component)]
+pub struct SettingsButton;
+
+/// Button changing a setting, to its next value.
+#[derive(Component, Clone, Copy)]
+pub enum SettingButton {
+    Music,
+    Sfx,
+    WindowSize,
+    Fullscreen,
+    Language,
+    Difficulty,
+    Inspector,
+}
+
+#[derive(Component)]
+pub struct SettingsBackButton;
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            window_scale: None,
+            fullscreen: false,
+            language: DEFAULT_LANGUAGE.to_string(),
+            difficulty: Difficulty::default(),
+            inspector: cfg!(debug_assertions),
+        }
+    }
+}
+
+impl Settings {
+    pub fn path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join(SETTINGS_FILE))
+    }
+
+    /// Load the settings saved in `path`, the default ones when there are none yet.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, SettingsError> {
+        match fs::read_to_string(path.into()) {
+            Ok(content) => toml::from_str(&content).map_err(SettingsError::Parse),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(SettingsError::Io(error)),
+        }
+    }
+
+    /// Load the settings of the user config directory. Problems give the
+    /// default settings, they are printed as the logs are not set up yet.
+    pub fn from_config_dir() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+
+        Self::load(&path).unwrap_or_else(|error| {
+            eprintln!("{}: {}", path.display(), error);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) -> Result<(), SettingsError> {
+        let Some(path) = Self::path() else {
+            return Ok(());
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(SettingsError::Io)?;
+        }
+
+        let content = toml::to_string_pretty(self).map_err(SettingsError::Serialize)?;
+        fs::write(path, content).map_err(SettingsError::Io)
+    }
+
+    pub fn window_mode(&self) -> WindowMode {
+        if self.fullscreen {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        }
+    }
+
+    fn label(&self, setting: SettingButton, words: &WordsResource) -> String {
+        let on_off = |on: bool| if on { "On" } else { "Off" };
+
+        match setting {
+            SettingButton::Music => format!("Music: {:.0}%", self.music_volume * 100.0),
+            SettingButton::Sfx => format!("Effects: {:.0}%", self.sfx_volume * 100.0),
+            SettingButton::WindowSize => match self.window_scale {
+                Some(scale) => format!(
+                    "Size: {}x{}",
+                    (WINDOW_WIDTH as f64 * scale).round(),
+                    (WINDOW_HEIGHT as f64 * scale).round()
+                ),
+                None => "Size: Auto".to_string(),
+            },
+            SettingButton::Fullscreen => format!("Fullscreen: {}", on_off(self.fullscreen)),
+            SettingButton::Language => format!("Language: {}", words.current_pack().name),
+            SettingButton::Difficulty => format!("Difficulty: {:?}", self.difficulty),
+            SettingButton::Inspector => format!("Inspector: {}", on_off(self.inspector)),
+        }
+    }
+
+    /// Change `setting` to its next value.
+    fn cycle(&mut self, setting: SettingButton, words: &mut WordsResource) {
+        let next_volume = |volume: f32| {
+            let step = (volume * VOLUME_STEPS).round() + 1.0;
+            (step % (VOLUME_STEPS + 1.0)) / VOLUME_STEPS
+        };
+
+        match setting {
+            SettingButton::Music => self.music_volume = next_volume(self.music_volume),
+            SettingButton::Sfx => self.sfx_volume = next_volume(self.sfx_volume),
+            SettingButton::WindowSize => {
+                // from the screen scale to the smallest size, and back
+                self.window_scale = match self.window_scale {
+                    None => Some(WINDOW_SCALES[0]),
+                    Some(scale) => WINDOW_SCALES.iter().copied().find(|next| *next > scale),
+                }
+            }
+            SettingButton::Fullscreen => self.fullscreen = !self.fullscreen,
+            SettingButton::Language => {
+                words.select_next();
+                self.language = words.current_pack().code.clone();
+            }
+            SettingButton::Difficulty => self.difficulty = self.difficulty.next(),
+            SettingButton::Inspector => self.inspector = !self.inspector,
+        }
+    }
+}
+
+pub struct SettingsPlugin;
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(apply_settings)
+            .add_system(setting_button_clicked)
+            .add_system(settings_back_clicked)
+            .add_system_set(
+                SystemSet::on_enter(GameState::Gameplay).with_system(choose_difficulty),
+            );
+    }
+}
+
+/// Spawn the settings over the whole screen, to be added to the menu showing
+/// them.
+pub fn spawn_settings_menu(
+    commands: &mut Commands,
+    font_server: &FontsAtlas,
+    settings: &Settings,
+    words: &WordsResource,
+) -> Entity {
+    let settings_root = commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            background_color: BackgroundColor(Color::WHITE),
+            ..default()
+        })
+        .insert(SettingsUIRoot)
+        .id();
+
+    for setting in [
+        SettingButton::Music,
+        SettingButton::Sfx,
+        SettingButton::WindowSize,
+        SettingButton::Fullscreen,
+        SettingButton::Language,
+        SettingButton::Difficulty,
+        SettingButton::Inspector,
+    ] {
+        let button = spawn_button(
+            commands,
+            font_server,
+            &settings.label(setting, words),
+            Color::rgb(0.9, 0.8, 0.4),
+        );
+        commands
+            .entity(button)
+            .insert(setting)
+            .set_parent(settings_root);
+    }
+
+    let back_button = spawn_button(commands, font_server, "Back", Color::rgb(0.4, 0.4, 0.9));
+    commands
+        .entity(back_button)
+        .insert(SettingsBackButton)
+        .set_parent(settings_root);
+
+    settings_root
+}
+
+#[allow(clippy::type_complexity)]
+fn setting_button_clicked(
+    interactions: Query<(&Interaction, &SettingButton, &Children), Changed<Interaction>>,
+    mut texts: Query<&mut Text>,
+    mut settings: ResMut<Settings>,
+    mut words: ResMut<WordsResource>,
+) {
+    for (interaction, setting, children) in &interactions {
+        if matches!(interaction, Interaction::Clicked) {
+            settings.cycle(*setting, &mut words);
+
+            let mut text = texts.get_mut(children[0]).unwrap();
+            text.sections[0].value = settings.label(*setting, &words);
+        }
+    }
+}
+
+fn settings_back_clicked(
+    mut commands: Commands,
+    interactions: Query<&Interaction, (With<SettingsBackButton>, Changed<Interaction>)>,
+    settings_root: Query<Entity, With<SettingsUIRoot>>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Clicked) {
+            for entity in settings_root.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
+
+/// Apply the settings when they change, and save them.
+fn apply_settings(
+    settings: Res<Settings>,
+    mut music_volume: ResMut<MusicVolume>,
+    mut sfx_volume: ResMut<SfxVolume>,
+    mut words: ResMut<WordsResource>,
+    mut windows: ResMut<Windows>,
+    inspector: Option<ResMut<WorldInspectorParams>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    music_volume.0 = settings.music_volume;
+    sfx_volume.0 = settings.sfx_volume;
+
+    if words.current_pack().code != settings.language && !words.select(&settings.language) {
+        warn!("no {} language pack", settings.language);
+    }
+
+    if let Some(window) = windows.get_mut(WindowId::primary()) {
+        if window.scale_factor_override() != settings.window_scale {
+            window.set_scale_factor_override(settings.window_scale);
+        }
+        if window.mode() != settings.window_mode() {
+            window.set_mode(settings.window_mode());
+        }
+    }
+
+    if let Some(mut inspector) = inspector {
+        if inspector.enabled != settings.inspector {
+            inspector.enabled = settings.inspector;
+        }
+    }
+
+    // loaded at startup, nothing new to save
+    if settings.is_added() {
+        return;
+    }
+    if let Err(error) = settings.save() {
+        error!("{}", error);
+    }
+}
+
+/// Solo runs are played at the chosen difficulty, a replay at the one it was
+/// recorded with, the daily challenge and the multiplayer games at the normal
+/// one.
+fn choose_difficulty(
+    settings: Res<Settings>,
+    mode: Res<GameMode>,
+    daily: Option<Res<DailyChallenge>>,
+    replay: Option<Res<ReplayPlayer>>,
+    mut difficulty: ResMut<RunDifficulty>,
+) {
+    difficulty.0 = match (replay, daily) {
+        (Some(replay), _) => replay.replay.difficulty,
+        (None, None) if *mode == GameMode::Solo => settings.difficulty,
+        _ => Difficulty::Normal,
+    };
+}
@@ -0,0 +1,376 @@
+use std::{fmt, time::Duration};
+
+use bevy::{
+    asset::AssetPlugin, audio::AudioSink, prelude::*, time::TimeUpdateStrategy, utils::Instant,
+    window::WindowId,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    assets::AssetsPlugin,
+    enemy::{ClaimedBy, Enemy, EnemyState, Target},
+    gameplay::GameplayPlugin,
+    player::{PlayerColor, PlayerScore, PlayerStats},
+    rng::{start_run, GameRng},
+    stage::StageComponent,
+    stats::StatsPlugin,
+    GameMode, GameState, WINDOW_HEIGHT,
+};
+
+pub const SIM_TICK_RATE: f64 = 60.0; // simulated frames per second of play
+pub const CHARS_PER_WORD: f32 = 5.0; // in the words per minute
+
+/// Typing skills of the bot playing the simulated runs.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct BotTypist {
+    pub wpm: f32,
+    pub error_rate: f32, // chance of a keystroke to be wrong, from 0.0 to 1.0
+    pub reaction: f32,   // delay before typing a new target, in seconds
+}
+
+impl Default for BotTypist {
+    fn default() -> Self {
+        Self {
+            wpm: 40.0,
+            error_rate: 0.05,
+            reaction: 0.6,
+        }
+    }
+}
+
+/// What the bot is up to in the current run.
+#[derive(Resource)]
+struct BotState {
+    rng: StdRng,
+    aim: Option<Entity>, // enemy about to be typed
+    next_key: f64,       // time of the next keystroke, in seconds
+}
+
+/// Result of a simulated run.
+#[derive(Debug, Clone, Copy)]
+pub struct RunReport {
+    pub seed: u64,
+    pub score: u32,
+    pub stage: u16,
+    pub play_time: f32,  // in seconds
+    pub game_over: bool, // `false` when the run was stopped at the time limit
+}
+
+/// Runs of many seeds, summed up by `Display`.
+#[derive(Debug, Clone, Default)]
+pub struct SimReport {
+    pub bot: BotTypist,
+    pub runs: Vec<RunReport>,
+}
+
+/// Solo runs without window, rendering nor audio output, played by a bot as
+/// fast as they are simulated. The frames last `1 / SIM_TICK_RATE` seconds.
+pub struct Simulation {
+    pub app: App,
+    instant: Instant,
+}
+
+impl BotTypist {
+    /// Delay between two keystrokes, in seconds.
+    pub fn keystroke_delay(&self) -> f64 {
+        60.0 / (self.wpm * CHARS_PER_WORD).max(1.0) as f64
+    }
+}
+
+/// The gameplay of a solo run without window, rendering nor audio output. The
+/// assets are handles that are never loaded.
+pub struct HeadlessPlugin;
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(TransformPlugin)
+            .add_plugin(HierarchyPlugin)
+            .add_plugin(AssetPlugin::default())
+            // what the rendering and audio plugins would register
+            .add_asset::<Image>()
+            .add_asset::<TextureAtlas>()
+            .add_asset::<Font>()
+            .add_asset::<AudioSource>()
+            .add_asset::<AudioSink>()
+            .init_resource::<Audio>()
+            .add_event::<ReceivedCharacter>()
+            .insert_resource(PlayerScore(0))
+            .insert_resource(PlayerStats::default())
+            .insert_resource(GameRng::new(None))
+            .init_resource::<GameMode>()
+            .init_resource::<PlayerColor>()
+            .add_state(GameState::MainMenu)
+            .add_plugin(AssetsPlugin)
+            .add_plugin(GameplayPlugin)
+            .add_plugin(StatsPlugin)
+            .add_system_set(SystemSet::on_enter(GameState::Gameplay).with_system(start_run));
+    }
+}
+
+impl Simulation {
+    pub fn new(bot: BotTypist) -> Self {
+        Self::from_app(App::new(), bot)
+    }
+
+    /// Simulate in `app`, e.g. with a `LogPlugin` already added.
+    pub fn from_app(mut app: App, bot: BotTypist) -> Self {
+        app.add_plugin(HeadlessPlugin)
+            .insert_resource(bot)
+            .add_plugin(BotPlugin);
+        Self::started(app)
+    }
+
+    /// Simulate in `app` without bot, the characters are sent by the caller.
+    pub fn headless(mut app: App) -> Self {
+        app.add_plugin(HeadlessPlugin);
+        Self::started(app)
+    }
+
+    fn started(app: App) -> Self {
+        let mut simulation = Self {
+            app,
+            instant: Instant::now(),
+        };
+        // startup, waiting in the main menu
+        simulation.step();
+        simulation
+    }
+
+    /// Play one frame.
+    pub fn step(&mut self) {
+        self.instant += Duration::from_secs_f64(1.0 / SIM_TICK_RATE);
+        self.app
+            .insert_resource(TimeUpdateStrategy::ManualInstant(self.instant));
+        self.app.update();
+    }
+
+    /// Play the frames of `seconds` seconds.
+    pub fn advance(&mut self, seconds: f32) {
+        let frames = (seconds as f64 * SIM_TICK_RATE).ceil() as usize;
+        for _ in 0..frames {
+            self.step();
+        }
+    }
+
+    pub fn state(&self) -> &GameState {
+        self.app.world.resource::<State<GameState>>().current()
+    }
+
+    /// Play the run of `seed` until the game is over, or for `max_play_time`
+    /// seconds.
+    pub fn run(&mut self, seed: u64, max_play_time: f32) -> RunReport {
+        self.app.world.resource_mut::<GameRng>().fixed_seed = Some(seed);
+        self.app
+            .world
+            .resource_mut::<State<GameState>>()
+            .set(GameState::Gameplay)
+            .unwrap();
+        self.step();
+
+        while *self.state() == GameState::Gameplay
+            && self.app.world.resource::<PlayerStats>().play_time < max_play_time
+        {
+            self.step();
+        }
+
+        let game_over = *self.state() == GameState::GameOver;
+        let world = &mut self.app.world;
+        let report = RunReport {
+            seed,
+            score: world.resource::<PlayerScore>().0,
+            stage: world
+                .query::<&StageComponent>()
+                .get_single(world)
+                .map(|stage| stage.level)
+                .unwrap_or(1),
+            play_time: world.resource::<PlayerStats>().play_time,
+            game_over,
+        };
+
+        // ready for the next run
+        if !game_over {
+            self.app
+                .world
+                .resource_mut::<State<GameState>>()
+                .set(GameState::GameOver)
+                .unwrap();
+            self.step();
+        }
+
+        report
+    }
+}
+
+/// Play the run of every seed of `seeds`, see `Simulation::run`.
+pub fn simulate(
+    bot: BotTypist,
+    seeds: impl IntoIterator<Item = u64>,
+    max_play_time: f32,
+) -> SimReport {
+    let mut simulation = Simulation::new(bot);
+    let runs = seeds
+        .into_iter()
+        .map(|seed| simulation.run(seed, max_play_time))
+        .collect();
+
+    SimReport { bot, runs }
+}
+
+impl SimReport {
+    /// Score below which `percent` of the runs are, from 0.0 to 100.0.
+    pub fn score_percentile(&self, percent: f32) -> u32 {
+        let mut scores = self.runs.iter().map(|run| run.score).collect::<Vec<_>>();
+        scores.sort_unstable();
+
+        let Some(last) = scores.len().checked_sub(1) else {
+            return 0;
+        };
+        let rank = (percent / 100.0 * last as f32).round() as usize;
+        scores[rank.min(last)]
+    }
+
+    pub fn mean_score(&self) -> f32 {
+        let total = self.runs.iter().map(|run| run.score as f32).sum::<f32>();
+        total / self.runs.len().max(1) as f32
+    }
+
+    pub fn mean_play_time(&self) -> f32 {
+        let total = self.runs.iter().map(|run| run.play_time).sum::<f32>();
+        total / self.runs.len().max(1) as f32
+    }
+
+    /// Number of runs by stage reached, from the first stage to the furthest.
+    pub fn stages(&self) -> Vec<(u16, usize)> {
+        let furthest = self.runs.iter().map(|run| run.stage).max().unwrap_or(1);
+        (1..=furthest)
+            .map(|stage| {
+                let runs = self.runs.iter().filter(|run| run.stage == stage).count();
+                (stage, runs)
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for SimReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} runs of a bot typing {} wpm, {:.0}% of errors, {:.2}s of reaction",
+            self.runs.len(),
+            self.bot.wpm,
+            self.bot.error_rate * 100.0,
+            self.bot.reaction
+        )?;
+
+        writeln!(f, "\nStage reached")?;
+        for (stage, runs) in self.stages() {
+            writeln!(f, "{stage:>5} {runs:>5} {}", "#".repeat(runs))?;
+        }
+
+        writeln!(f, "\nScore")?;
+        writeln!(
+            f,
+            "  min {}, 25% {}, median {}, 75% {}, max {}, mean {:.0}",
+            self.score_percentile(0.0),
+            self.score_percentile(25.0),
+            self.score_percentile(50.0),
+            self.score_percentile(75.0),
+            self.score_percentile(100.0),
+            self.mean_score()
+        )?;
+
+        let stopped = self.runs.iter().filter(|run| !run.game_over).count();
+        writeln!(f, "\nMean play time {:.0}s", self.mean_play_time())?;
+        write!(f, "{stopped} runs stopped at the time limit")
+    }
+}
+
+pub struct BotPlugin;
+impl Plugin for BotPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(BotState {
+            rng: StdRng::seed_from_u64(0),
+            aim: None,
+            next_key: 0.0,
+        })
+        .add_system_to_stage(CoreStage::PreUpdate, bot_type.before("key_pressed"))
+        .add_system_set(SystemSet::on_enter(GameState::Gameplay).with_system(reset_bot));
+    }
+}
+
+/// The mistakes of a run are the same every time it is simulated.
+fn reset_bot(mut bot: ResMut<BotState>, rng: Res<GameRng>) {
+    bot.rng = StdRng::seed_from_u64(rng.fixed_seed.unwrap_or_default());
+    bot.aim = None;
+    bot.next_key = 0.0;
+}
+
+/// Type the word of the lowest enemy, a key after the other. Like a player,
+/// the bot takes some time to react to a new target and sometimes misses.
+#[allow(clippy::type_complexity)]
+fn bot_type(
+    skills: Res<BotTypist>,
+    mut bot: ResMut<BotState>,
+    time: Res<Time>,
+    enemies: Query<
+        (Entity, &Enemy, &GlobalTransform, &Children, Option<&Target>),
+        Without<ClaimedBy>,
+    >,
+    texts: Query<&Text>,
+    mut inputs: EventWriter<ReceivedCharacter>,
+    game_state: Res<State<GameState>>,
+) {
+    let now = time.elapsed_seconds_f64();
+    if *game_state.current() != GameState::Gameplay || now < bot.next_key {
+        return;
+    }
+
+    let next_char = |children: &Children| {
+        texts
+            .get(children[0])
+            .ok()
+            .and_then(|text| text.sections[0].value.chars().next())
+    };
+
+    // the current target first, then the lowest enemy on screen
+    let target = enemies
+        .iter()
+        .filter(|(_, enemy, transform, children, _)| {
+            enemy.state != EnemyState::Idle
+                && transform.translation().y < WINDOW_HEIGHT / 2.0
+                && next_char(children).is_some()
+        })
+        .min_by(|a, b| {
+            (a.4.is_none(), a.2.translation().y)
+                .partial_cmp(&(b.4.is_none(), b.2.translation().y))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+    let Some((entity, _, _, children, targeted)) = target else {
+        bot.aim = None;
+        return;
+    };
+
+    if targeted.is_none() && bot.aim != Some(entity) {
+        bot.aim = Some(entity);
+        bot.next_key = now + skills.reaction as f64;
+        return;
+    }
+
+    let expected = next_char(children).unwrap_or(' ');
+    let typed = if bot.rng.gen::<f32>() < skills.error_rate {
+        // any other letter
+        let offset = bot.rng.gen_range(1..26);
+        let letter = (expected.to_ascii_lowercase() as u32).wrapping_sub('a' as u32);
+        char::from_u32('a' as u32 + (letter.min(25) + offset) % 26).unwrap_or('?')
+    } else {
+        expected
+    };
+
+    inputs.send(ReceivedCharacter {
+        id: WindowId::primary(),
+        char: typed,
+    });
+    bot.aim = Some(entity);
+    bot.next_key = now + skills.keystroke_delay();
+}
@@ -4,9 +4,11 @@ use bevy::prelude::*;
 
 use crate::{
     assets::FontsAtlas,
-    enemy::{MIN_SPAWN_TIME_INTERVAL, SPAWN_TIME_INTERVAL},
-    player::{PlayerScore, PlayerStats, PlayerCombosChanged},
-    GameState, CASE_SIZE, WINDOW_HEIGHT,
+    levels::{Levels, Wave},
+    mixer::SoundEffect,
+    player::{PlayerCombosChanged, PlayerScore, PlayerStats},
+    rules::{self, IGLOO_HEALTH},
+    GameMode, GameState, CASE_SIZE, WINDOW_HEIGHT,
 };
 
 pub const TEXT_FONT_SIZE: f32 = 30.0;
@@ -23,13 +25,67 @@ pub struct StageComponent {
     pub enemies_alive: usize,
     pub spawn_timer: Timer,
 
+    pub health: u32, // of the igloo, the game is over at 0
+
     bonus_ratio: f32, // bonus to apply when an enemy is defeated. Grow with combos
+
+    waves: Vec<Wave>, // of the current level
+    wave: usize,      // next wave to spawn from
+    wave_spawned: usize,
 }
 
 impl StageComponent {
+    pub fn first_level(levels: &Levels) -> Self {
+        let mut stage = Self {
+            level: 1,
+            health: IGLOO_HEALTH,
+            ..default()
+        };
+        stage.load_waves(levels);
+        stage
+    }
+
+    fn load_waves(&mut self, levels: &Levels) {
+        self.waves = levels.plan(self.level);
+        self.wave = 0;
+        self.wave_spawned = 0;
+        self.enemies_to_defeat = self.waves.iter().map(|wave| wave.count).sum();
+
+        self.spawn_delay_of_next_wave();
+        self.spawn_timer.set_mode(TimerMode::Repeating);
+        self.spawn_timer.reset();
+    }
+
+    fn spawn_delay_of_next_wave(&mut self) {
+        while self
+            .waves
+            .get(self.wave)
+            .is_some_and(|wave| self.wave_spawned >= wave.count)
+        {
+            self.wave += 1;
+            self.wave_spawned = 0;
+        }
+
+        if let Some(wave) = self.waves.get(self.wave) {
+            self.spawn_timer
+                .set_duration(Duration::from_secs_f32(wave.spawn_delay));
+        }
+    }
+
+    /// Wave of the next enemy to spawn, `None` once the level spawned them all.
+    pub fn next_spawn(&mut self) -> Option<Wave> {
+        let wave = self.waves.get(self.wave)?.clone();
+
+        self.wave_spawned += 1;
+        self.spawn_delay_of_next_wave();
+
+        Some(wave)
+    }
+
     pub fn enemy_defeated(&mut self) {
         self.enemies_defeated += 1;
-        self.enemies_alive -= 1;
+        // a replicated stage can be reset by the server while enemies are alive
+        self.enemies_alive = self.enemies_alive.saturating_sub(1);
     }
     pub fn enemy_born(&mut self, long_ennemy: bool) {
         self.enemies_alive += 1;
@@ -40,14 +96,10 @@ impl StageComponent {
     }
 
     pub fn increase_bonus(&mut self) {
-        self.bonus_ratio += 0.2;
+        self.bonus_ratio = rules::increased_bonus(self.bonus_ratio);
     }
     pub fn decrease_bonus(&mut self) {
-        if self.bonus_ratio <= 0.0 {
-            return;
-        }
-
-        self.bonus_ratio -= 0.2;
+        self.bonus_ratio = rules::decreased_bonus(self.bonus_ratio);
     }
 
     pub fn bonus(&self) -> f32 {
@@ -55,13 +107,16 @@ impl StageComponent {
     }
 
     pub fn can_spawn_enemy(&self) -> bool {
-        self.enemies_alive + self.enemies_defeated < self.enemies_to_defeat
+        rules::can_spawn_enemy(
+            self.enemies_alive,
+            self.enemies_defeated,
+            self.enemies_to_defeat,
+        )
     }
     pub fn can_spawn_long_enemy(&self) -> bool {
         self.can_spawn_enemy()
             && self.long_enemies_alive + self.long_enemies_defeated
-                // < ((self.level as f32 - 1.0) * 0.8) as usize
-                < ( self.level / 3 ) as usize
+                < rules::max_long_enemies(self.level)
     }
 
     pub fn reset(&mut self) {
@@ -75,19 +130,31 @@ impl StageComponent {
         self.spawn_timer.reset();
     }
 
-    pub fn next_level(&mut self) {
+    pub fn is_cleared(&self) -> bool {
+        rules::is_stage_cleared(
+            self.enemies_alive,
+            self.enemies_defeated,
+            self.enemies_to_defeat,
+        )
+    }
+
+    pub fn level_up(&mut self, levels: &Levels) {
+        self.reset();
         self.level += 1;
+        self.load_waves(levels);
+        self.health = rules::healed_igloo(self.health);
+    }
 
-        let duration: u64 = (self.spawn_timer.duration().as_millis() as f32 * 0.85) as u64;
-        self.spawn_timer.set_duration(Duration::from_millis(
-            if duration < MIN_SPAWN_TIME_INTERVAL {
-                MIN_SPAWN_TIME_INTERVAL
-            } else {
-                duration
-            },
-        ));
-        self.spawn_timer.reset();
-        self.enemies_to_defeat = (self.enemies_to_defeat as f32 * 2.25) as usize;
+    /// An enemy reached the igloo, it is gone but doesn't give any point.
+    pub fn igloo_hit(&mut self, damage: u32, generated: bool) {
+        self.health = rules::damaged_igloo(self.health, damage);
+        if !generated {
+            self.enemy_defeated();
+        }
+    }
+
+    pub fn is_igloo_destroyed(&self) -> bool {
+        self.health == 0
     }
 }
 
@@ -103,6 +170,9 @@ pub struct StageScoreText;
 #[derive(Component)]
 pub struct PlayerCombosText;
 
+#[derive(Component)]
+pub struct IglooHealthText;
+
 pub struct StagePlugin;
 impl Plugin for StagePlugin {
     fn build(&self, app: &mut App) {
@@ -113,12 +183,16 @@ impl Plugin for StagePlugin {
         .add_system_set(
             SystemSet::on_update(GameState::Gameplay).with_system(animate_score_changing),
         )
-        .add_system_set(SystemSet::on_update(GameState::Gameplay).with_system(stage_update))
+        .add_system_set(
+            SystemSet::on_update(GameState::Gameplay)
+                .with_system(stage_progress.label("stage_progress"))
+                .with_system(stage_update.after("stage_progress")),
+        )
         .add_system_set(SystemSet::on_update(GameState::Gameplay).with_system(combos_update));
     }
 }
 
-fn draw_stage_data(mut commands: Commands, fonts: Res<FontsAtlas>) {
+fn draw_stage_data(mut commands: Commands, fonts: Res<FontsAtlas>, levels: Res<Levels>) {
     let stage_name = "Stage ".to_string();
     let stage_level = format!("{}", 1);
 
@@ -150,15 +224,7 @@ fn draw_stage_data(mut commands: Commands, fonts: Res<FontsAtlas>) {
             transform: Transform::from_translation(Vec3::new(x, y, z)),
             ..default()
         })
-        .insert(StageComponent {
-            level: 1,
-            enemies_to_defeat: 2,
-            spawn_timer: Timer::new(
-                Duration::from_millis(SPAWN_TIME_INTERVAL),
-                TimerMode::Repeating,
-            ),
-            ..default()
-        })
+        .insert(StageComponent::first_level(&levels))
         .insert(StageLevelText {
             timer: Timer::new(Duration::from_millis(700), TimerMode::Once),
             animate: false,
@@ -221,37 +287,90 @@ fn draw_stage_data(mut commands: Commands, fonts: Res<FontsAtlas>) {
             ..default()
         })
         .insert(PlayerCombosText);
+
+    commands
+        .spawn(Text2dBundle {
+            text: Text::from_sections([
+                TextSection::new(
+                    "Igloo ",
+                    TextStyle {
+                        font: fonts.common_font.clone(),
+                        font_size: 20.0,
+                        color: Color::BLACK,
+                    },
+                ),
+                TextSection::new(
+                    format!("{IGLOO_HEALTH}/{IGLOO_HEALTH}"),
+                    TextStyle {
+                        font: fonts.common_font.clone(),
+                        font_size: 20.0,
+                        color: Color::BLACK,
+                    },
+                ),
+            ])
+            .with_alignment(TextAlignment::BOTTOM_LEFT),
+            transform: Transform::from_translation(Vec3::new(x, y - 90.0, z)),
+            ..default()
+        })
+        .insert(IglooHealthText);
 }
 
-fn stage_update(
+/// Level up once every enemy of the stage is defeated.
+pub fn stage_progress(
     mut stage: Query<&mut StageComponent, Changed<StageComponent>>,
+    mode: Res<GameMode>,
+    levels: Res<Levels>,
+) {
+    for mut stage in stage.iter_mut() {
+        // in multiplayer, the server may decide when the stage is cleared
+        if mode.levels_up_locally() && stage.is_cleared() {
+            stage.level_up(&levels);
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn stage_update(
+    stage: Query<&StageComponent, Changed<StageComponent>>,
     mut level_text: Query<(&mut Text, &mut StageLevelText)>,
     mut score_text: Query<&mut Text, (With<StageScoreText>, Without<StageLevelText>)>,
+    mut health_text: Query<
+        &mut Text,
+        (
+            With<IglooHealthText>,
+            Without<StageLevelText>,
+            Without<StageScoreText>,
+        ),
+    >,
     player_score: Res<PlayerScore>,
+    mut sounds: EventWriter<SoundEffect>,
 ) {
     if stage.is_empty() {
         return;
     }
 
-    let mut stage = stage.single_mut();
+    let stage = stage.single();
     let (mut level_text, mut animation) = level_text.single_mut();
     let mut score_text = score_text.single_mut();
 
-    if stage.enemies_defeated >= stage.enemies_to_defeat && stage.enemies_alive == 0 {
-        stage.reset();
-        stage.next_level();
-        stage.enemies_to_defeat = stage.level as usize;
+    let level = format!("{}", stage.level);
+    if level_text.sections[1].value != level {
+        level_text.sections[1].value = level;
         animation.animate = true;
-    }
 
-    level_text.sections[1].value = format!("{}", stage.level);
+        // not for the first level of a new run
+        if stage.level > 1 {
+            sounds.send(SoundEffect::LevelUp);
+        }
+    }
     score_text.sections[1].value = format!("{}", player_score.0);
+    health_text.single_mut().sections[1].value = format!("{}/{}", stage.health, IGLOO_HEALTH);
 }
 
 fn combos_update(
     mut commands: Commands,
-    mut combos: Query<( Entity , &mut Text), With<PlayerCombosChanged>>,
-    player_stats: Res<PlayerStats>
+    mut combos: Query<(Entity, &mut Text), With<PlayerCombosChanged>>,
+    player_stats: Res<PlayerStats>,
 ) {
     if combos.is_empty() {
         return;
@@ -259,7 +378,7 @@ fn combos_update(
 
     let mut combos = combos.single_mut();
 
-    combos.1.sections[1].value = format!("{}", player_stats.combos_count);
+    combos.1.sections[1].value = format!("{}", player_stats.combo.count);
 
     commands.entity(combos.0).remove::<PlayerCombosChanged>();
 }
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    assets::FontsAtlas,
+    enemy::{Enemy, EnemyHit, EnemyState, TargetLocked},
+    gameover::GameoverUIRoot,
+    player::PlayerStats,
+    GameState,
+};
+
+/// Keyboard rows of the heatmap, keys missed outside of them get rows of their own.
+pub const KEYBOARD_ROWS: [&str; 4] = ["1234567890", "qwertyuiop", "asdfghjkl", "zxcvbnm"];
+pub const KEY_SIZE: f32 = 36.0; // in px
+pub const OTHER_KEYS_PER_ROW: usize = 10;
+pub const MAX_OTHER_KEYS: usize = 20; // the most missed ones, the heatmap stays on screen
+
+#[derive(Component)]
+pub struct TypingStatsUIRoot;
+
+pub struct StatsPlugin;
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Gameplay)
+                .with_system(count_play_time)
+                .with_system(record_reactions)
+                .with_system(record_hits),
+        )
+        .add_system_set(SystemSet::on_update(GameState::GameOver).with_system(spawn_typing_stats));
+    }
+}
+
+fn count_play_time(time: Res<Time>, mut player_stats: ResMut<PlayerStats>) {
+    player_stats.play_time += time.delta_seconds();
+}
+
+/// Enemies are timed from the play time at which they appeared, they may be
+/// gone before a component could be inserted.
+fn record_reactions(
+    mut locked: EventReader<TargetLocked>,
+    spawned: Query<Entity, Added<Enemy>>,
+    enemies: Query<(), With<Enemy>>,
+    mut spawned_at: Local<HashMap<Entity, f32>>,
+    mut player_stats: ResMut<PlayerStats>,
+) {
+    for entity in spawned.iter() {
+        spawned_at.insert(entity, player_stats.play_time);
+    }
+
+    for TargetLocked { enemy } in locked.iter() {
+        if let Some(spawned_at) = spawned_at.remove(enemy) {
+            player_stats.reaction_time += player_stats.play_time - spawned_at;
+            player_stats.reactions += 1;
+        }
+    }
+
+    spawned_at.retain(|entity, _| enemies.contains(*entity));
+}
+
+/// Hits are counted as finished once the enemy has no character left to type.
+fn record_hits(
+    mut hits: EventReader<EnemyHit>,
+    enemies: Query<&Enemy>,
+    mut typed: Local<HashMap<Entity, u32>>,
+    mut player_stats: ResMut<PlayerStats>,
+) {
+    for hit in hits.iter() {
+        player_stats.hits += 1;
+        *typed.entry(hit.enemy).or_default() += 1;
+
+        if enemies
+            .get(hit.enemy)
+            .is_ok_and(|enemy| enemy.state == EnemyState::Idle)
+        {
+            player_stats.finished_chars += typed.remove(&hit.enemy).unwrap_or_default();
+        }
+    }
+
+    // words left unfinished
+    typed.retain(|entity, _| enemies.contains(*entity));
+}
+
+fn spawn_typing_stats(
+    mut commands: Commands,
+    root: Query<Entity, With<GameoverUIRoot>>,
+    stats_root: Query<Entity, With<TypingStatsUIRoot>>,
+    player_stats: Res<PlayerStats>,
+    font_server: Res<FontsAtlas>,
+) {
+    if !stats_root.is_empty() || root.is_empty() {
+        return;
+    }
+
+    let text_style = |font_size| TextStyle {
+        font: font_server.common_font.clone(),
+        font_size,
+        color: Color::BLACK,
+    };
+
+    let reaction_time = match player_stats.mean_reaction_time() {
+        Some(reaction_time) => format!("{reaction_time:.2}s"),
+        None => "-".to_string(),
+    };
+    let summary = format!(
+        "{:.0} WPM - max combo {}\nAccuracy {:.0}% raw, {:.0}% net\nReaction time {}",
+        player_stats.wpm(),
+        player_stats.combo.max,
+        player_stats.raw_accuracy() * 100.0,
+        player_stats.net_accuracy() * 100.0,
+        reaction_time
+    );
+
+    let mut rows: Vec<Vec<char>> = KEYBOARD_ROWS
+        .iter()
+        .map(|row| row.chars().collect())
+        .collect();
+    let mut others: Vec<(char, u32)> = player_stats
+        .key_misses
+        .iter()
+        .filter(|(key, _)| !KEYBOARD_ROWS.iter().any(|row| row.contains(**key)))
+        .map(|(key, misses)| (*key, *misses))
+        .collect();
+    // most missed first, the sort keeps the key order of equal ones
+    others.sort_by_key(|(_, misses)| std::cmp::Reverse(*misses));
+    others.truncate(MAX_OTHER_KEYS);
+    let others: Vec<char> = others.into_iter().map(|(key, _)| key).collect();
+    rows.extend(others.chunks(OTHER_KEYS_PER_ROW).map(<[char]>::to_vec));
+
+    let max_misses = player_stats.key_misses.values().copied().max().unwrap_or(0);
+
+    let stats_root = commands
+        .spawn(NodeBundle {
+            style: Style {
+                align_self: AlignSelf::Center,
+                flex_direction: FlexDirection::Column,
+                margin: UiRect::all(Val::Percent(2.0)),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(TypingStatsUIRoot)
+        .with_children(|commands| {
+            commands.spawn(TextBundle {
+                style: Style {
+                    align_self: AlignSelf::Center,
+                    margin: UiRect::bottom(Val::Px(10.0)),
+                    ..default()
+                },
+                text: Text::from_section(summary, text_style(20.0))
+                    .with_alignment(TextAlignment::CENTER),
+                ..default()
+            });
+
+            // the more a key was missed, the redder it is
+            for row in rows {
+                commands
+                    .spawn(NodeBundle {
+                        style: Style {
+                            align_self: AlignSelf::Center,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|commands| {
+                        for key in row {
+                            let misses = player_stats.key_misses.get(&key).copied().unwrap_or(0);
+                            let heat = if max_misses > 0 {
+                                misses as f32 / max_misses as f32
+                            } else {
+                                0.0
+                            };
+
+                            commands
+                                .spawn(NodeBundle {
+                                    style: Style {
+                                        min_size: Size::new(Val::Px(KEY_SIZE), Val::Px(KEY_SIZE)),
+                                        padding: UiRect::horizontal(Val::Px(4.0)),
+                                        margin: UiRect::all(Val::Px(2.0)),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        ..default()
+                                    },
+                                    background_color: BackgroundColor(Color::rgb(
+                                        0.9,
+                                        0.9 * (1.0 - heat),
+                                        0.9 * (1.0 - heat),
+                                    )),
+                                    ..default()
+                                })
+                                .with_children(|commands| {
+                                    commands.spawn(TextBundle::from_section(
+                                        key_label(key),
+                                        text_style(20.0),
+                                    ));
+                                });
+                        }
+                    });
+            }
+        })
+        .id();
+
+    commands.entity(root.single()).add_child(stats_root);
+}
+
+/// Text of a key on the heatmap, the invisible ones are named.
+fn key_label(key: char) -> String {
+    match key {
+        ' ' => "space".to_string(),
+        key if key.is_whitespace() => format!("U+{:04X}", key as u32),
+        key => key.to_string(),
+    }
+}
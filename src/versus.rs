@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_renet::renet::RenetClient;
+use rand::Rng;
+
+use crate::{
+    archetypes::EnemyArchetypes,
+    assets::FontsAtlas,
+    defeat_zone::IglooHit,
+    enemy::{spawn_enemy, Enemy, EnemyKilled, EnemyKind, GeneratedEnemy},
+    gameover::GameoverUIRoot,
+    levels::Levels,
+    lobby::ConnectionLost,
+    network::{encode, ClientMessage, PlayerInfo, ServerMessage, RELIABLE_CHANNEL},
+    request_game_over,
+    rng::GameRng,
+    stage::StageComponent,
+    words::WordsResource,
+    GameMode, GameState, CASE_SIZE, WINDOW_HEIGHT, WINDOW_WIDTH,
+};
+
+/// Archetype of the spiders sent by the opponent, the basic enemy when missing.
+pub const SENT_ENEMY: &str = "spiderling";
+
+#[derive(Resource)]
+pub struct VersusMatch {
+    pub opponent: String,
+    pub result: Option<VersusResult>,
+    enemies: HashMap<Entity, u32>, // id reported to the server of the enemies alive
+    next_enemy: u32,
+}
+
+impl VersusMatch {
+    pub fn new(me: u64, players: &[PlayerInfo]) -> Self {
+        let opponent = players
+            .iter()
+            .find(|player| player.id != me)
+            .map(|player| player.name.clone())
+            .unwrap_or_default();
+
+        Self {
+            opponent,
+            result: None,
+            enemies: HashMap::new(),
+            next_enemy: 0,
+        }
+    }
+}
+
+pub enum VersusResult {
+    Winner { id: u64, name: String },
+    ConnectionLost,
+}
+
+#[derive(Component)]
+pub struct VersusResultText;
+
+pub struct VersusPlugin;
+impl Plugin for VersusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Gameplay)
+                .with_system(send_stage_events)
+                .with_system(apply_level_up)
+                .with_system(spawn_opponent_spiders)
+                .with_system(receive_game_over)
+                .with_system(lose_versus),
+        )
+        .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(report_defeat))
+        .add_system_set(
+            SystemSet::on_update(GameState::GameOver)
+                .with_system(receive_game_over)
+                .with_system(lose_versus)
+                .with_system(show_versus_result),
+        );
+    }
+}
+
+fn lose_versus(
+    mut lost: EventReader<ConnectionLost>,
+    versus: Option<ResMut<VersusMatch>>,
+    mut state: ResMut<State<GameState>>,
+) {
+    let Some(mut versus) = versus else {
+        return;
+    };
+
+    if lost.iter().next().is_some() && versus.result.is_none() {
+        versus.result = Some(VersusResult::ConnectionLost);
+        request_game_over(&mut state);
+    }
+}
+
+/// The server counts the enemies and gives the points, it is only told which
+/// enemy spawned and how it left the field.
+#[allow(clippy::type_complexity)]
+fn send_stage_events(
+    client: Option<ResMut<RenetClient>>,
+    versus: Option<ResMut<VersusMatch>>,
+    mut killed: EventReader<EnemyKilled>,
+    mut hits: EventReader<IglooHit>,
+    born: Query<(Entity, &EnemyKind, Option<&GeneratedEnemy>), Added<Enemy>>,
+    archetypes: Res<EnemyArchetypes>,
+) {
+    let (Some(mut client), Some(mut versus)) = (client, versus) else {
+        return;
+    };
+
+    for (entity, kind, generated) in born.iter() {
+        let enemy = versus.next_enemy;
+        versus.next_enemy += 1;
+        versus.enemies.insert(entity, enemy);
+
+        let message = ClientMessage::EnemyBorn {
+            enemy,
+            archetype: archetypes[kind.0].name.clone(),
+            generated: generated.is_some(),
+        };
+        client.send_message(RELIABLE_CHANNEL, encode(&message));
+    }
+
+    for killed in killed.iter() {
+        if let Some(enemy) = versus.enemies.remove(&killed.enemy) {
+            let message = ClientMessage::EnemyDefeated { enemy };
+            client.send_message(RELIABLE_CHANNEL, encode(&message));
+        }
+    }
+
+    for hit in hits.iter() {
+        if let Some(enemy) = versus.enemies.remove(&hit.enemy) {
+            let message = ClientMessage::EnemyReachedIgloo { enemy };
+            client.send_message(RELIABLE_CHANNEL, encode(&message));
+        }
+    }
+}
+
+/// Level up decided by the server, for the versus players and the co-op guests.
+fn apply_level_up(
+    mut messages: EventReader<ServerMessage>,
+    mut stage: Query<&mut StageComponent>,
+    mode: Res<GameMode>,
+    levels: Res<Levels>,
+) {
+    if mode.levels_up_locally() {
+        return;
+    }
+
+    for message in messages.iter() {
+        if let ServerMessage::LevelUp { level } = message {
+            let mut stage = stage.single_mut();
+            stage.level_up(&levels);
+
+            if stage.level != *level {
+                warn!(
+                    "stage {} is out of sync with the server ({})",
+                    stage.level, level
+                );
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_opponent_spiders(
+    mut commands: Commands,
+    mut messages: EventReader<ServerMessage>,
+    stage: Query<&StageComponent>,
+    time: Res<Time>,
+    archetypes: Res<EnemyArchetypes>,
+    fonts: Res<FontsAtlas>,
+    words: Res<WordsResource>,
+    mut rng: ResMut<GameRng>,
+) {
+    for message in messages.iter() {
+        let ServerMessage::SendSpiders { count } = message else {
+            continue;
+        };
+
+        let stage = stage.single();
+        let kind = archetypes.find(SENT_ENEMY).unwrap_or_default();
+        let spider = &archetypes[kind];
+        let rng = &mut rng.others;
+
+        for _ in 0..*count {
+            let position_x_percent = rng.gen_range(-0.8..=0.8);
+            let position = Vec2::new(
+                position_x_percent * WINDOW_WIDTH / 2.0,
+                WINDOW_HEIGHT / 2.0 + CASE_SIZE / 1.75,
+            );
+
+            // sent spiders do not count in the stage progression
+            let enemy = spawn_enemy(
+                &mut commands,
+                time.elapsed_seconds_f64(),
+                position,
+                spider.speed.at_level(stage.level),
+                spider.points,
+                spider.word(&words, rng),
+                EnemyKind(kind),
+                &archetypes,
+                &fonts,
+            );
+            commands.entity(enemy).insert(GeneratedEnemy);
+        }
+    }
+}
+
+fn receive_game_over(
+    mut messages: EventReader<ServerMessage>,
+    versus: Option<ResMut<VersusMatch>>,
+    mut state: ResMut<State<GameState>>,
+) {
+    let Some(mut versus) = versus else {
+        return;
+    };
+
+    for message in messages.iter() {
+        if let ServerMessage::GameOver { winner_id, winner } = message {
+            versus.result = Some(VersusResult::Winner {
+                id: *winner_id,
+                name: winner.clone(),
+            });
+            request_game_over(&mut state);
+        }
+    }
+}
+
+/// An enemy reached our defeat zone before the server ended the game.
+fn report_defeat(versus: Option<Res<VersusMatch>>, client: Option<ResMut<RenetClient>>) {
+    let (Some(versus), Some(mut client)) = (versus, client) else {
+        return;
+    };
+
+    if versus.result.is_none() {
+        client.send_message(RELIABLE_CHANNEL, encode(&ClientMessage::Defeated));
+    }
+}
+
+fn show_versus_result(
+    mut commands: Commands,
+    versus: Option<Res<VersusMatch>>,
+    client: Option<Res<RenetClient>>,
+    root: Query<Entity, With<GameoverUIRoot>>,
+    result_text: Query<Entity, With<VersusResultText>>,
+    font_server: Res<FontsAtlas>,
+) {
+    let Some(versus) = versus else {
+        return;
+    };
+
+    if !result_text.is_empty() || root.is_empty() {
+        return;
+    }
+
+    let text = match &versus.result {
+        Some(VersusResult::Winner { id, .. }) if client.map(|c| c.client_id()) == Some(*id) => {
+            format!("You beat {}!", versus.opponent)
+        }
+        Some(VersusResult::Winner { name, .. }) => format!("{} wins!", name),
+        Some(VersusResult::ConnectionLost) => "Connection lost".to_string(),
+        None => return,
+    };
+
+    let text = commands
+        .spawn(TextBundle {
+            style: Style {
+                align_self: AlignSelf::Center,
+                margin: UiRect::all(Val::Percent(3.0)),
+                ..default()
+            },
+            text: Text::from_section(
+                text,
+                TextStyle {
+                    font: font_server.common_font.clone(),
+                    font_size: 40.0,
+                    color: Color::BLACK,
+                },
+            ),
+            ..default()
+        })
+        .insert(VersusResultText)
+        .id();
+
+    commands.entity(root.single()).add_child(text);
+}
@@ -1,11 +1,40 @@
+use std::{fmt, fs, io, path::Path};
+
 use bevy::prelude::*;
 use rand::Rng;
 
+use crate::assets::asset_path;
+
+pub const WORDS_DIR: &str = "words";
+pub const DEFAULT_LANGUAGE: &str = "en";
+pub const SHORT_WORD_MAX_LEN: usize = 8; // words without section longer than this are long words
+pub const SEGMENT_SEPARATOR: char = '|'; // between the segments of a sentence
+const BUILTIN_SENTENCE_SEGMENTS: usize = 3; // segments of the sentences made of short words
+
+const BUILTIN_SHORT_WORDS: [&str; 5] = ["short", "should", "be", "not", "long"];
+const BUILTIN_LONG_WORDS: [&str; 1] = ["longword"];
+const BUILTIN_SPECIAL_CHARS: [char; 30] = [
+    '!', '@', '#', '$', '%', '^', '&', '*', '(', ')', '-', '_', '=', '+', '[', ']', '{', '}', ';',
+    ':', '\'', '"', ',', '<', '>', '.', '/', '?', '\\', '|',
+];
+
+/// Every installed language pack, and the one used for the next games.
 #[derive(Resource, Default)]
 pub struct WordsResource {
-    short_words: Vec<&'static str>,
-    long_words: Vec<&'static str>,
-    special_chars: Vec<&'static str>,
+    packs: Vec<LanguagePack>,
+    current: usize,
+}
+
+/// Word pools and keyboard characters of one language.
+#[derive(Default)]
+pub struct LanguagePack {
+    pub code: String,
+    pub name: String,
+    pub alphabet: Vec<char>, // characters allowed in short and long words, anything when empty
+    short_words: Vec<String>,
+    long_words: Vec<String>,
+    special_chars: Vec<char>,
+    sentences: Vec<Vec<String>>, // typed segment by segment
 }
 
 #[derive(Component)]
@@ -13,49 +42,449 @@ pub struct TextEnemy {
     pub enemy_entity_id: Entity,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum WordsSection {
+    Unsorted, // before any section header, sorted by length
+    Name,
+    Alphabet,
+    Short,
+    Long,
+    Special,
+    Sentences,
+}
+
+#[derive(Debug)]
+pub enum WordsFileError {
+    Io(io::Error),
+    UnknownSection { line: usize, name: String },
+    InvalidEntry { line: usize, entry: String },
+    OutsideAlphabet { line: usize, entry: String },
+    EmptyPool(&'static str),
+    IncompatiblePool(&'static str), // empty, and the built-in one is outside the alphabet
+}
+
+impl fmt::Display for WordsFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WordsFileError::Io(error) => write!(f, "cannot read words file: {error}"),
+            WordsFileError::UnknownSection { line, name } => {
+                write!(f, "line {line}: unknown section [{name}]")
+            }
+            WordsFileError::InvalidEntry { line, entry } => {
+                write!(f, "line {line}: invalid entry {entry:?}")
+            }
+            WordsFileError::OutsideAlphabet { line, entry } => {
+                write!(
+                    f,
+                    "line {line}: {entry:?} uses characters outside the alphabet"
+                )
+            }
+            WordsFileError::EmptyPool(pool) => write!(f, "no {pool} words, using built-in list"),
+            WordsFileError::IncompatiblePool(pool) => {
+                write!(f, "no {pool} words in the alphabet, pack skipped")
+            }
+        }
+    }
+}
+
 impl WordsResource {
-    pub fn short_word(&self) -> &'static str {
-        let mut thread_rng = rand::thread_rng();
-        let index = thread_rng.gen_range(0..self.short_words.len());
-        self.short_words[index]
+    /// Load every `*.txt` language pack of `dir`, relative to the assets folder.
+    /// Falls back to the built-in English pack when none is usable.
+    pub fn from_dir(dir: impl AsRef<Path>) -> Self {
+        let dir = asset_path(dir);
+
+        let mut paths = match fs::read_dir(&dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+                .collect::<Vec<_>>(),
+            Err(error) => {
+                warn!("{}: {}", dir.display(), WordsFileError::Io(error));
+                vec![]
+            }
+        };
+        paths.sort();
+
+        let mut packs = paths
+            .iter()
+            .filter_map(|path| {
+                LanguagePack::from_file(path)
+                    .map_err(|error| warn!("{}: {}", path.display(), error))
+                    .ok()
+            })
+            .collect::<Vec<_>>();
+
+        if packs.is_empty() {
+            packs.push(LanguagePack::builtin());
+        }
+
+        let current = packs
+            .iter()
+            .position(|pack| pack.code == DEFAULT_LANGUAGE)
+            .unwrap_or(0);
+
+        Self { packs, current }
+    }
+
+    pub fn current_pack(&self) -> &LanguagePack {
+        &self.packs[self.current]
     }
 
-    pub fn long_word(&self) -> &'static str {
-        let mut thread_rng = rand::thread_rng();
-        let index = thread_rng.gen_range(0..self.long_words.len());
-        self.long_words[index]
+    /// Select the installed pack of the language `code`, if there is one.
+    pub fn select(&mut self, code: &str) -> bool {
+        let Some(index) = self.packs.iter().position(|pack| pack.code == code) else {
+            return false;
+        };
+
+        self.current = index;
+        true
     }
 
-    pub fn special_word(&self) -> String {
-        let mut thread_rng = rand::thread_rng();
-        let numbers_of_chars = thread_rng.gen_range(3..7);
+    /// Cycle to the next installed pack.
+    pub fn select_next(&mut self) {
+        self.current = (self.current + 1) % self.packs.len();
+    }
+
+    pub fn short_word(&self, rng: &mut impl Rng) -> &str {
+        self.current_pack().short_word(rng)
+    }
+
+    pub fn long_word(&self, rng: &mut impl Rng) -> &str {
+        self.current_pack().long_word(rng)
+    }
+
+    pub fn special_word(&self, rng: &mut impl Rng) -> String {
+        self.current_pack().special_word(rng)
+    }
+
+    pub fn sentence(&self, rng: &mut impl Rng) -> Vec<String> {
+        self.current_pack().sentence(rng)
+    }
+}
+
+impl LanguagePack {
+    pub fn short_word(&self, rng: &mut impl Rng) -> &str {
+        let index = rng.gen_range(0..self.short_words.len());
+        &self.short_words[index]
+    }
+
+    pub fn long_word(&self, rng: &mut impl Rng) -> &str {
+        let index = rng.gen_range(0..self.long_words.len());
+        &self.long_words[index]
+    }
+
+    pub fn special_word(&self, rng: &mut impl Rng) -> String {
+        let numbers_of_chars = rng.gen_range(3..7);
 
         let mut word = String::from("");
 
         for _ in 0..numbers_of_chars {
-            let index = thread_rng.gen_range(0..self.special_chars.len());
+            let index = rng.gen_range(0..self.special_chars.len());
 
-            word.push_str(self.special_chars[index]);
+            word.push(self.special_chars[index]);
         }
 
         word
     }
 
-    pub fn from_file() -> Self {
+    /// Segments of a sentence, made of short words when the pack has none.
+    pub fn sentence(&self, rng: &mut impl Rng) -> Vec<String> {
+        if !self.sentences.is_empty() {
+            let index = rng.gen_range(0..self.sentences.len());
+            return self.sentences[index].clone();
+        }
+
+        (0..BUILTIN_SENTENCE_SEGMENTS)
+            .map(|_| format!("{} {}", self.short_word(rng), self.short_word(rng)))
+            .collect()
+    }
+
+    pub fn builtin() -> Self {
         Self {
-            short_words: vec!["short", "should", "be", "not", "long"],
-            long_words: vec!["longword"],
-            special_chars: vec![
-                "!", "@", "#", "$", "%", "^", "&", "*", "(", ")", "-", "_", "=", "+", "[", "]",
-                "{", "}", ";", ":", "'", "\"", ",", "<", ">", ".", "/", "?", "\\", "|",
-            ],
+            code: DEFAULT_LANGUAGE.to_string(),
+            name: "English".to_string(),
+            alphabet: vec![],
+            short_words: BUILTIN_SHORT_WORDS.map(String::from).to_vec(),
+            long_words: BUILTIN_LONG_WORDS.map(String::from).to_vec(),
+            special_chars: BUILTIN_SPECIAL_CHARS.to_vec(),
+            sentences: vec![],
+        }
+    }
+
+    /// Load a language pack file, its code is the file name without extension.
+    /// Invalid lines are reported and skipped, see `parse`.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, WordsFileError> {
+        let path = path.as_ref();
+        let code = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| DEFAULT_LANGUAGE.to_string());
+
+        let content = fs::read_to_string(path).map_err(WordsFileError::Io)?;
+
+        let (mut pack, errors) = Self::parse(&content)?;
+        for error in errors {
+            warn!("{}: {}", path.display(), error);
+        }
+
+        if pack.name.is_empty() {
+            pack.name = code.clone();
         }
+        pack.code = code;
+
+        Ok(pack)
+    }
+
+    /// Parse a language pack: one entry per line, lines starting with `#` are
+    /// comments and the optional `[name]`, `[alphabet]`, `[short]`, `[long]`
+    /// `[special]` and `[sentences]` headers select what the next lines
+    /// describe. Special entries are single characters, `\#` for `#`,
+    /// sentences are words separated by spaces and split in segments by `|`.
+    ///
+    /// An empty pool falls back to the built-in one when it fits in the
+    /// alphabet of the pack, the pack is rejected otherwise. The other errors
+    /// are the skipped lines.
+    pub fn parse(content: &str) -> Result<(Self, Vec<WordsFileError>), WordsFileError> {
+        let mut pack = Self::default();
+        let mut errors = vec![];
+        let mut section = WordsSection::Unsorted;
+        let mut words = vec![];
+        let mut sentences = vec![];
+
+        for (index, line) in content.lines().enumerate() {
+            let line_number = index + 1;
+            let entry = line.trim();
+
+            if entry.is_empty() || entry.starts_with('#') {
+                continue;
+            }
+            // an escaped `#` is not a comment
+            let entry = if entry == "\\#" { "#" } else { entry };
+
+            if let Some(name) = entry.strip_prefix('[').and_then(|e| e.strip_suffix(']')) {
+                section = match name.trim() {
+                    "name" => WordsSection::Name,
+                    "alphabet" => WordsSection::Alphabet,
+                    "short" => WordsSection::Short,
+                    "long" => WordsSection::Long,
+                    "special" => WordsSection::Special,
+                    "sentences" => WordsSection::Sentences,
+                    _ => {
+                        errors.push(WordsFileError::UnknownSection {
+                            line: line_number,
+                            name: name.to_string(),
+                        });
+                        continue;
+                    }
+                };
+                continue;
+            }
+
+            if section == WordsSection::Name {
+                pack.name = entry.to_string();
+                continue;
+            }
+
+            if section == WordsSection::Sentences {
+                let segments = entry
+                    .split(SEGMENT_SEPARATOR)
+                    .map(|segment| segment.split_whitespace().collect::<Vec<_>>().join(" "))
+                    .collect::<Vec<_>>();
+
+                if segments.iter().any(String::is_empty) {
+                    errors.push(WordsFileError::InvalidEntry {
+                        line: line_number,
+                        entry: line.to_string(),
+                    });
+                } else {
+                    sentences.push((line_number, segments));
+                }
+                continue;
+            }
+
+            if entry.chars().any(char::is_whitespace)
+                || (section == WordsSection::Special && entry.chars().count() != 1)
+            {
+                errors.push(WordsFileError::InvalidEntry {
+                    line: line_number,
+                    entry: line.to_string(),
+                });
+                continue;
+            }
+
+            match section {
+                WordsSection::Alphabet => pack.alphabet.extend(entry.chars()),
+                WordsSection::Special => pack.special_chars.extend(entry.chars()),
+                _ => words.push((line_number, section, entry.to_string())),
+            }
+        }
+
+        // the alphabet can be declared anywhere in the file, so words are checked last
+        for (line, section, word) in words {
+            if !pack.alphabet.is_empty() && !word.chars().all(|c| pack.alphabet.contains(&c)) {
+                errors.push(WordsFileError::OutsideAlphabet { line, entry: word });
+                continue;
+            }
+
+            match section {
+                WordsSection::Long => pack.long_words.push(word),
+                WordsSection::Unsorted if word.chars().count() > SHORT_WORD_MAX_LEN => {
+                    pack.long_words.push(word)
+                }
+                _ => pack.short_words.push(word),
+            }
+        }
+
+        for (line, segments) in sentences {
+            let in_alphabet = |segment: &String| {
+                segment
+                    .chars()
+                    .all(|c| c == ' ' || pack.alphabet.contains(&c))
+            };
+            if !pack.alphabet.is_empty() && !segments.iter().all(in_alphabet) {
+                errors.push(WordsFileError::OutsideAlphabet {
+                    line,
+                    entry: segments.join(" | "),
+                });
+                continue;
+            }
+
+            pack.sentences.push(segments);
+        }
+
+        // the words of another language are only borrowed if they can be typed
+        let builtin = Self::builtin();
+        let fits_alphabet = |words: &[String]| {
+            pack.alphabet.is_empty()
+                || words
+                    .iter()
+                    .all(|word| word.chars().all(|c| pack.alphabet.contains(&c)))
+        };
+        let short_fallback = fits_alphabet(&builtin.short_words);
+        let long_fallback = fits_alphabet(&builtin.long_words);
+
+        if pack.short_words.is_empty() {
+            if !short_fallback {
+                return Err(WordsFileError::IncompatiblePool("short"));
+            }
+            errors.push(WordsFileError::EmptyPool("short"));
+            pack.short_words = builtin.short_words;
+        }
+        if pack.long_words.is_empty() {
+            if !long_fallback {
+                return Err(WordsFileError::IncompatiblePool("long"));
+            }
+            errors.push(WordsFileError::EmptyPool("long"));
+            pack.long_words = builtin.long_words;
+        }
+        // typed whatever the language
+        if pack.special_chars.is_empty() {
+            errors.push(WordsFileError::EmptyPool("special"));
+            pack.special_chars = builtin.special_chars;
+        }
+
+        Ok((pack, errors))
     }
 }
 
 pub struct WordsPlugin;
 impl Plugin for WordsPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(WordsResource::from_file());
+        app.insert_resource(WordsResource::from_dir(WORDS_DIR));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> (LanguagePack, Vec<WordsFileError>) {
+        LanguagePack::parse(content).unwrap()
+    }
+
+    #[test]
+    fn sections_fill_their_pool() {
+        let (pack, errors) = parse(
+            "[name]\nFrançais\n[short]\nchat\n[long]\nordinateur\n[special]\n?\n\
+             [sentences]\nle chat | dort\n",
+        );
+
+        assert!(errors.is_empty());
+        assert_eq!(pack.name, "Français");
+        assert_eq!(pack.short_words, ["chat"]);
+        assert_eq!(pack.long_words, ["ordinateur"]);
+        assert_eq!(pack.special_chars, ['?']);
+        assert_eq!(pack.sentences, [vec!["le chat", "dort"]]);
+    }
+
+    #[test]
+    fn unsorted_words_are_sorted_by_length() {
+        let (pack, _) = parse("penguins\nsnowballs\n");
+
+        assert_eq!(pack.short_words, ["penguins"]);
+        assert_eq!(pack.long_words, ["snowballs"]);
+    }
+
+    #[test]
+    fn comments_are_skipped() {
+        let (pack, errors) =
+            parse("# pack\n#comment\n[short]\nice\n[long]\nsnowflake\n[special]\n\\#\n");
+
+        assert!(errors.is_empty());
+        assert_eq!(pack.short_words, ["ice"]);
+        assert_eq!(pack.special_chars, ['#']);
+    }
+
+    #[test]
+    fn invalid_lines_are_reported() {
+        let (pack, errors) = parse("[short]\nice\ntwo words\n[long]\nsnowflake\n[oops]\n");
+
+        assert_eq!(pack.short_words, ["ice"]);
+        assert!(matches!(
+            errors[..],
+            [
+                WordsFileError::InvalidEntry { line: 3, .. },
+                WordsFileError::UnknownSection { line: 6, .. },
+                WordsFileError::EmptyPool("special"),
+            ]
+        ));
+    }
+
+    #[test]
+    fn words_outside_the_alphabet_are_skipped() {
+        let (pack, errors) = parse(
+            "[alphabet]\nabcdefghijklmnopqrstuvwxyz\n[short]\nice\nglaçon\n[long]\nsnowflake\n",
+        );
+
+        assert_eq!(pack.short_words, ["ice"]);
+        assert!(matches!(
+            errors[0],
+            WordsFileError::OutsideAlphabet { line: 5, .. }
+        ));
+    }
+
+    #[test]
+    fn empty_pools_fall_back_to_the_builtin_ones() {
+        let (pack, errors) = parse("[alphabet]\nabcdefghijklmnopqrstuvwxyz\n[short]\nice\n");
+
+        assert_eq!(pack.long_words, BUILTIN_LONG_WORDS);
+        assert_eq!(pack.special_chars, BUILTIN_SPECIAL_CHARS);
+        assert!(matches!(
+            errors[..],
+            [
+                WordsFileError::EmptyPool("long"),
+                WordsFileError::EmptyPool("special"),
+            ]
+        ));
+    }
+
+    #[test]
+    fn pack_of_another_alphabet_does_not_borrow_english_words() {
+        let result = LanguagePack::parse("[alphabet]\nабвгдеж\n[short]\nваж\n");
+
+        assert!(matches!(
+            result,
+            Err(WordsFileError::IncompatiblePool("long"))
+        ));
     }
 }
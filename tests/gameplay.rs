@@ -0,0 +1,280 @@
+//! The gameplay systems played headless, frame by frame, with keystrokes sent
+//! by the tests instead of a player.
+
+use bevy::{ecs::system::SystemState, prelude::*, window::WindowId};
+use penguin_game::{
+    archetypes::EnemyArchetypes,
+    assets::FontsAtlas,
+    defeat_zone::{DEFEAT_ZONE_HEIGHT, DEFEAT_ZONE_Y},
+    enemy::{spawn_enemy, Enemy, EnemyKind, Target},
+    music::MusicPlayer,
+    player::{PlayerScore, PlayerStats},
+    rng::GameRng,
+    rules::IGLOO_HEALTH,
+    sim::Simulation,
+    stage::StageComponent,
+    words::TextEnemy,
+    GameState,
+};
+
+struct TestGame(Simulation);
+
+impl TestGame {
+    /// A solo run with only the enemies of the test on the field.
+    fn start() -> Self {
+        let mut simulation = Simulation::headless(App::new());
+        let world = &mut simulation.app.world;
+        world.resource_mut::<GameRng>().fixed_seed = Some(0);
+        world
+            .resource_mut::<State<GameState>>()
+            .set(GameState::Gameplay)
+            .unwrap();
+        simulation.step();
+
+        let mut game = Self(simulation);
+        game.stage_mut().spawn_timer.pause();
+        game
+    }
+
+    fn world(&mut self) -> &mut World {
+        &mut self.0.app.world
+    }
+
+    /// Spawn an enemy of the first archetype, counted by the stage like the
+    /// planned ones.
+    fn spawn_enemy(&mut self, word: &str, position: Vec2, velocity: f32) -> Entity {
+        let world = self.world();
+        let mut state = SystemState::<(
+            Commands,
+            Res<FontsAtlas>,
+            Res<EnemyArchetypes>,
+            Query<&mut StageComponent>,
+        )>::new(world);
+        let (mut commands, fonts, archetypes, mut stage) = state.get_mut(world);
+
+        let enemy = spawn_enemy(
+            &mut commands,
+            0.0,
+            position,
+            velocity,
+            archetypes[0].points,
+            word,
+            EnemyKind(0),
+            &archetypes,
+            &fonts,
+        );
+        stage.single_mut().enemy_born(false);
+        state.apply(world);
+
+        // on screen, where the keystrokes look for their target
+        self.0.step();
+        enemy
+    }
+
+    /// Type `keys`, a keystroke per frame.
+    fn type_keys(&mut self, keys: &str) {
+        for char in keys.chars() {
+            self.world()
+                .resource_mut::<Events<ReceivedCharacter>>()
+                .send(ReceivedCharacter {
+                    id: WindowId::primary(),
+                    char,
+                });
+            self.0.step();
+        }
+    }
+
+    /// Type `keys` in a single frame, like a fast typist at a low frame rate.
+    fn type_burst(&mut self, keys: &str) {
+        let mut events = self.world().resource_mut::<Events<ReceivedCharacter>>();
+        for char in keys.chars() {
+            events.send(ReceivedCharacter {
+                id: WindowId::primary(),
+                char,
+            });
+        }
+        self.0.step();
+    }
+
+    fn enemy(&mut self, entity: Entity) -> Option<&Enemy> {
+        self.world().get::<Enemy>(entity)
+    }
+
+    fn is_target(&mut self, entity: Entity) -> bool {
+        self.world().get::<Target>(entity).is_some()
+    }
+
+    /// What is left to type of the word of an enemy.
+    fn word(&mut self, entity: Entity) -> String {
+        let world = self.world();
+        let mut texts = world.query::<(&TextEnemy, &Text)>();
+        texts
+            .iter(world)
+            .find(|(text_enemy, _)| text_enemy.enemy_entity_id == entity)
+            .map(|(_, text)| text.sections[0].value.clone())
+            .unwrap()
+    }
+
+    fn stage(&mut self) -> &StageComponent {
+        let world = self.world();
+        let mut stage = world.query::<&StageComponent>();
+        stage.single(world)
+    }
+
+    fn stage_mut(&mut self) -> Mut<'_, StageComponent> {
+        let world = self.world();
+        let mut stage = world.query::<&mut StageComponent>();
+        stage.single_mut(world)
+    }
+
+    fn state(&self) -> &GameState {
+        self.0.state()
+    }
+}
+
+#[test]
+fn first_letter_targets_the_lowest_matching_enemy() {
+    let mut game = TestGame::start();
+    let high = game.spawn_enemy("pebble", Vec2::new(-100.0, 200.0), 0.0);
+    let low = game.spawn_enemy("penguin", Vec2::new(100.0, 0.0), 0.0);
+    let other = game.spawn_enemy("igloo", Vec2::new(0.0, -100.0), 0.0);
+
+    game.type_keys("p");
+
+    assert!(game.is_target(low));
+    assert!(!game.is_target(high));
+    assert!(!game.is_target(other));
+    assert_eq!(game.word(low), "enguin");
+    assert_eq!(game.word(high), "pebble");
+}
+
+#[test]
+fn target_is_kept_until_its_word_is_typed() {
+    let mut game = TestGame::start();
+    let penguin = game.spawn_enemy("penguin", Vec2::new(0.0, 0.0), 0.0);
+    let igloo = game.spawn_enemy("igloo", Vec2::new(0.0, -100.0), 0.0);
+
+    game.type_keys("pei");
+
+    // the `i` of the igloo is a miss, not a new target
+    assert!(game.is_target(penguin));
+    assert!(!game.is_target(igloo));
+    assert_eq!(game.word(penguin), "nguin");
+    assert_eq!(game.word(igloo), "igloo");
+    assert_eq!(game.world().resource::<PlayerStats>().combo.count, 0);
+
+    game.type_keys("nguin");
+
+    assert!(!game.is_target(penguin));
+}
+
+#[test]
+fn keys_typed_in_the_same_frame_are_all_applied() {
+    let mut game = TestGame::start();
+    let penguin = game.spawn_enemy("penguin", Vec2::new(0.0, 0.0), 0.0);
+
+    game.type_burst("pengu");
+
+    assert!(game.is_target(penguin));
+    assert_eq!(game.word(penguin), "in");
+
+    game.type_burst("inice");
+
+    // the keys after the word look for a new target
+    assert!(!game.is_target(penguin));
+    assert_eq!(game.word(penguin), "");
+    assert_eq!(game.world().resource::<PlayerStats>().misses, 3);
+}
+
+#[test]
+fn fish_landing_decreases_enemy_life() {
+    let mut game = TestGame::start();
+    let enemy = game.spawn_enemy("fish", Vec2::new(0.0, 100.0), 0.0);
+
+    game.type_keys("f");
+
+    // the fish is still flying
+    assert_eq!(game.enemy(enemy).unwrap().life, 4);
+
+    game.0.advance(1.0);
+
+    assert_eq!(game.enemy(enemy).unwrap().life, 3);
+}
+
+#[test]
+fn typed_enemy_gives_its_points_when_dead() {
+    let mut game = TestGame::start();
+    let enemy = game.spawn_enemy("ice", Vec2::new(0.0, 100.0), 0.0);
+    let points = game.enemy(enemy).unwrap().points;
+
+    game.type_keys("ice");
+
+    assert_eq!(game.world().resource::<PlayerScore>().0, points);
+    assert_eq!(game.world().resource::<PlayerStats>().combo.count, 1);
+
+    // gone once the last fish lands
+    game.0.advance(1.0);
+
+    assert!(game.enemy(enemy).is_none());
+}
+
+#[test]
+fn clearing_the_stage_levels_up() {
+    let mut game = TestGame::start();
+    game.stage_mut().enemies_to_defeat = 1;
+    game.spawn_enemy("snow", Vec2::new(0.0, 100.0), 0.0);
+
+    assert_eq!(game.stage().level, 1);
+
+    game.type_keys("snow");
+    game.0.advance(0.1);
+
+    let stage = game.stage();
+    assert_eq!(stage.level, 2);
+    assert_eq!(stage.enemies_defeated, 0);
+    assert!(stage.enemies_to_defeat > 0);
+}
+
+#[test]
+fn enemy_reaching_the_defeat_zone_hits_the_igloo() {
+    let mut game = TestGame::start();
+    let position = Vec2::new(0.0, DEFEAT_ZONE_Y + DEFEAT_ZONE_HEIGHT / 2.0 + 5.0);
+    let enemy = game.spawn_enemy("spider", position, 1.0);
+
+    assert!(game.enemy(enemy).is_some());
+
+    game.0.advance(2.0);
+
+    assert!(game.enemy(enemy).is_none());
+    assert_eq!(game.stage().health, IGLOO_HEALTH - 1);
+    assert_eq!(*game.state(), GameState::Gameplay);
+}
+
+#[test]
+fn game_is_over_when_the_igloo_is_destroyed() {
+    let mut game = TestGame::start();
+    game.stage_mut().health = 1;
+    game.spawn_enemy("spider", Vec2::new(0.0, DEFEAT_ZONE_Y), 0.0);
+
+    game.0.advance(0.1);
+
+    assert_eq!(*game.state(), GameState::GameOver);
+}
+
+#[test]
+fn music_intensity_rises_as_an_enemy_nears_the_igloo() {
+    let mut game = TestGame::start();
+    game.spawn_enemy("far", Vec2::new(0.0, 200.0), 0.0);
+
+    game.0.advance(2.0);
+
+    let calm = game.world().resource::<MusicPlayer>().intensity();
+    assert!(calm < 0.3, "{calm}");
+
+    let position = Vec2::new(0.0, DEFEAT_ZONE_Y + DEFEAT_ZONE_HEIGHT / 2.0 + 20.0);
+    game.spawn_enemy("near", position, 0.0);
+    game.0.advance(2.0);
+
+    let tense = game.world().resource::<MusicPlayer>().intensity();
+    assert!(tense > 0.8, "{tense}");
+}